@@ -141,6 +141,8 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         // we use semantic_add so that the redundancy, symmetry and congruence checks run on it.
         let t = syn_enode_fresh.weak_shape();
         self.raw_add_to_class(i, t.clone(), i);
+        self.changes.new_enodes += 1;
+        self.mark_dirty(i);
         self.pending.insert(t.0);
         self.rebuild();
 
@@ -162,6 +164,7 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
             let usages = &mut self.classes.get_mut(&ref_id).unwrap().usages;
             usages.insert(sh.clone());
         }
+        *self.op_index.entry(operator(&sh)).or_default().entry(id).or_default() += 1;
     }
 
     pub(in crate::egraph) fn raw_remove_from_class(&mut self, id: Id, sh: L) -> ProvenSourceNode {
@@ -176,9 +179,29 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
             usages.remove(&sh);
         }
 
+        let op = operator(&sh);
+        let ids = self.op_index.get_mut(&op).unwrap();
+        let count = ids.get_mut(&id).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.op_index.remove(&op);
+            }
+        }
+
         opt_psn.unwrap()
     }
 
+    // Removes all e-nodes of `i`, so that it has no terms anymore.
+    // The public API never creates such e-classes, but the extractors have to handle them.
+    #[cfg(test)]
+    pub(crate) fn clear_class(&mut self, i: Id) {
+        let shapes: Vec<L> = self.classes[&i].nodes.keys().cloned().collect();
+        for sh in shapes {
+            self.raw_remove_from_class(i, sh);
+        }
+    }
 }
 
 impl<L: Language, N: Analysis<L>> EGraph<L, N> {
@@ -202,9 +225,10 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
             slots: slots.clone(),
             usages: HashSet::default(),
             syn_enode: syn_enode.clone(),
-            analysis_data: N::make(&self, &syn_enode),
+            analysis_data: N::make(&AnalysisView::new(self), &syn_enode),
         };
         self.classes.insert(c_id, c);
+        self.analysis_pending.insert(c_id);
 
         { // add syn_enode to the hashcons.
             let (sh, bij) = syn_enode.weak_shape();
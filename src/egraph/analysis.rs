@@ -1,12 +1,182 @@
 use crate::*;
 
+use std::any::Any;
+
 /// E-Graph Analysis allows you to propagate information upwards through the E-Graph.
-pub trait Analysis<L: Language>: Eq + Clone {
-    fn make(eg: &EGraph<L, Self>, enode: &L) -> Self;
-    fn merge(l: Self, r: Self) -> Self;
+///
+/// The analysis data of an e-class is expressed in terms of its own slots, see [EGraph::analysis_data_applied].
+/// `make` is called with e-nodes that use the slots of their e-class.
+///
+/// Tuples of analyses are analyses as well, which allows you to run multiple analyses on the same e-graph.
+/// The e-graph tracks changes per e-class and not per component, so a tuple calls the `modify` of every component
+/// whenever any of its components has changed.
+pub trait Analysis<L: Language>: Clone {
+    /// Computes the analysis data of an e-node.
+    ///
+    /// The analysis `N` of the e-graph might be bigger than `Self`, e.g. a tuple containing `Self`.
+    /// `eg` only gives access to the `Self` part of it.
+    fn make<N: Analysis<L>>(eg: &AnalysisView<L, N, Self>, enode: &L) -> Self;
+
+    /// Merges `r` into `l`, and reports which of the two has changed.
+    ///
+    /// The e-graph only propagates analysis data to the parents of an e-class, if `l` has actually changed.
+    fn merge(l: &mut Self, r: Self) -> DidMerge;
+
+    /// Called whenever the analysis data of the e-class `i` changed.
+    ///
+    /// This allows the analysis to change the e-graph itself, e.g. constant folding can add the computed constant to `i`.
+    /// It is called once the e-graph is rebuilt, so it can safely use [EGraph::add] and [EGraph::union] through [AnalysisMut::egraph].
+    ///
+    /// Like in `make`, the analysis `N` of the e-graph might be bigger than `Self`.
+    /// In that case `modify` may also be called when only another component changed, so it should not rely on `Self` having changed.
+    fn modify<N: Analysis<L>>(eg: &mut AnalysisMut<L, N, Self>, i: Id) {
+        let _ = (eg, i);
+    }
+
+    /// Renames the slots mentioned by this analysis data.
+    ///
+    /// Slot-aware analyses, whose data mentions slots (like the set of free variables that are actually used),
+    /// need to implement this. The default implementation leaves the data unchanged.
+    ///
+    /// `m` covers all slots of the e-class. Slots not covered by `m` have become redundant, and should be dropped.
+    fn apply_slotmap(&self, m: &SlotMap) -> Self {
+        let _ = m;
+        self.clone()
+    }
+
+    /// Returns the component of type `T` of this analysis data, if there is one.
+    ///
+    /// For tuples of analyses this looks into the components, e.g. `eg.analysis_data(i).get::<ConstProp>()`.
+    fn get<T: Any>(&self) -> Option<&T> where Self: 'static {
+        (self as &dyn Any).downcast_ref()
+    }
 }
 
 impl<L: Language> Analysis<L> for () {
-    fn make(eg: &EGraph<L, Self>, _: &L) {}
-    fn merge(l: (), r: ()) -> () {}
+    fn make<N: Analysis<L>>(eg: &AnalysisView<L, N, ()>, _: &L) {}
+    fn merge(l: &mut (), r: ()) -> DidMerge { DidMerge(false, false) }
+}
+
+/// The result of [Analysis::merge].
+///
+/// `DidMerge(a, b)` means that the merged data differs from the old `l` (if `a`), or from `r` (if `b`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DidMerge(pub bool, pub bool);
+
+impl std::ops::BitOr for DidMerge {
+    type Output = DidMerge;
+
+    fn bitor(self, other: DidMerge) -> DidMerge {
+        DidMerge(self.0 || other.0, self.1 || other.1)
+    }
+}
+
+/// Read access to the analysis `A` within an e-graph with analysis `N`.
+///
+/// This is what [Analysis::make] gets to see. If the e-graph is an `EGraph<L, (A, B)>`, then `A::make` sees the first component.
+pub struct AnalysisView<'a, 'p, L: Language, N: Analysis<L>, A> {
+    eg: &'a EGraph<L, N>,
+    proj: &'p dyn Fn(&'a N) -> &'a A,
+}
+
+impl<'a, L: Language, N: Analysis<L>> AnalysisView<'a, 'a, L, N, N> {
+    pub(crate) fn new(eg: &'a EGraph<L, N>) -> Self {
+        AnalysisView { eg, proj: &|x| x }
+    }
+}
+
+impl<'a, L: Language, N: Analysis<L>, A> AnalysisView<'a, '_, L, N, A> {
+    /// The underlying e-graph.
+    pub fn egraph(&self) -> &'a EGraph<L, N> {
+        self.eg
+    }
+
+    /// Like [EGraph::analysis_data], but only returns the `A` part.
+    pub fn analysis_data(&self, i: Id) -> &'a A {
+        (self.proj)(self.eg.analysis_data(i))
+    }
+
+    /// Like [EGraph::analysis_data_applied], but only returns the `A` part.
+    pub fn analysis_data_applied(&self, i: &AppliedId) -> A where A: Analysis<L> {
+        let i = self.eg.find_applied_id(i);
+        self.analysis_data(i.id).apply_slotmap(&i.m)
+    }
 }
+
+/// Write access to an e-graph with analysis `N`, while looking at its analysis `A`.
+///
+/// This is what [Analysis::modify] gets to see. If the e-graph is an `EGraph<L, (A, B)>`, then `A::modify` sees the first component.
+pub struct AnalysisMut<'a, 'p, L: Language, N: Analysis<L>, A> {
+    eg: &'a mut EGraph<L, N>,
+
+    // As the e-graph is borrowed mutably, the projection returns a copy of the analysis data.
+    proj: &'p dyn Fn(&N) -> A,
+}
+
+impl<'a, L: Language, N: Analysis<L>> AnalysisMut<'a, 'a, L, N, N> {
+    pub(crate) fn new(eg: &'a mut EGraph<L, N>) -> Self {
+        AnalysisMut { eg, proj: &|x| x.clone() }
+    }
+}
+
+impl<L: Language, N: Analysis<L>, A> AnalysisMut<'_, '_, L, N, A> {
+    /// The underlying e-graph.
+    pub fn egraph(&mut self) -> &mut EGraph<L, N> {
+        self.eg
+    }
+
+    /// Like [EGraph::analysis_data], but only returns the `A` part.
+    pub fn analysis_data(&self, i: Id) -> A {
+        (self.proj)(self.eg.analysis_data(i))
+    }
+
+    /// Like [EGraph::analysis_data_applied], but only returns the `A` part.
+    pub fn analysis_data_applied(&self, i: &AppliedId) -> A where A: Analysis<L> {
+        let i = self.eg.find_applied_id(i);
+        self.analysis_data(i.id).apply_slotmap(&i.m)
+    }
+}
+
+macro_rules! tuple_analysis {
+    ($($T:ident $i:tt),*) => {
+        impl<L: Language, $($T: Analysis<L>),*> Analysis<L> for ($($T,)*) {
+            fn make<N: Analysis<L>>(eg: &AnalysisView<L, N, Self>, enode: &L) -> Self {
+                ($({
+                    let proj = |x| &(eg.proj)(x).$i;
+                    $T::make(&AnalysisView { eg: eg.eg, proj: &proj }, enode)
+                },)*)
+            }
+
+            // each component modifies the e-graph on its own, whether its own data has changed or not.
+            // An earlier component might have merged `i` away, so we normalize it in between.
+            fn modify<N: Analysis<L>>(eg: &mut AnalysisMut<L, N, Self>, i: Id) {
+                let outer = eg.proj;
+                $({
+                    let proj = move |x: &N| outer(x).$i;
+                    let i = eg.eg.find_id(i);
+                    $T::modify(&mut AnalysisMut { eg: &mut *eg.eg, proj: &proj }, i);
+                })*
+            }
+
+            // the tuple has changed if any of its components has changed.
+            fn merge(l: &mut Self, r: Self) -> DidMerge {
+                let mut did = DidMerge(false, false);
+                $(did = did | $T::merge(&mut l.$i, r.$i);)*
+                did
+            }
+
+            fn apply_slotmap(&self, m: &SlotMap) -> Self {
+                ($(self.$i.apply_slotmap(m),)*)
+            }
+
+            fn get<T: Any>(&self) -> Option<&T> where Self: 'static {
+                (self as &dyn Any).downcast_ref()
+                    $(.or_else(|| self.$i.get()))*
+            }
+        }
+    }
+}
+
+tuple_analysis!(A 0, B 1);
+tuple_analysis!(A 0, B 1, C 2);
+tuple_analysis!(A 0, B 1, C 2, D 3);
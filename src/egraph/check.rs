@@ -56,6 +56,7 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         // And also checks that each Shape comes up in at most one EClass!
         let mut hashcons = HashMap::default();
         let mut usages = HashMap::default();
+        let mut op_index: HashMap<L, HashMap<Id, usize>> = HashMap::default();
 
         for (i, _) in &self.classes {
             usages.insert(*i, HashSet::default());
@@ -88,6 +89,7 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
             for sh in c.nodes.keys() {
                 assert!(!hashcons.contains_key(sh));
                 hashcons.insert(sh.clone(), *i);
+                *op_index.entry(operator(sh)).or_default().entry(*i).or_default() += 1;
 
                 for ref_id in sh.ids() {
                     usages.get_mut(&ref_id).unwrap()
@@ -97,6 +99,7 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         }
 
         assert_eq!(hashcons, self.hashcons);
+        assert_eq!(op_index, self.op_index);
         for (i, c) in &self.classes {
             assert_eq!(usages[&i], c.usages);
         }
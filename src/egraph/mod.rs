@@ -19,6 +19,7 @@ mod analysis;
 pub use analysis::*;
 
 use std::sync::Mutex;
+use std::collections::BTreeMap;
 
 /// Each E-Class can be understood "semantically" or "syntactically":
 /// - semantically means that it respects the equations already in the e-graph, and hence doesn't differentiate between equal things.
@@ -69,6 +70,10 @@ pub struct EGraph<L: Language, N: Analysis<L> = ()> {
     // For each shape contained in the EGraph, maps to the EClass that contains it.
     hashcons: HashMap<L, Id>,
 
+    // For each operator, the e-classes containing e-nodes with that operator, and how many of them.
+    // Operators are computed by `operator`, and are used to quickly find the e-classes that a pattern could match.
+    op_index: HashMap<L, HashMap<Id, usize>>,
+
     // For each (syn_slotset applied) non-normalized (i.e. "syntactic") weak shape, find the e-class who has this as syn_enode.
     // TODO remove this if explanations are disabled.
     syn_hashcons: HashMap<L, AppliedId>,
@@ -76,10 +81,32 @@ pub struct EGraph<L: Language, N: Analysis<L> = ()> {
     // E-Nodes that need to be re-processed, stored as shapes.
     pending: HashSet<L>,
 
+    // E-Classes whose analysis data changed, and that need to be passed to `Analysis::modify`.
+    analysis_pending: HashSet<Id>,
+
+    // E-Classes that changed since the last call to `clear_dirty`.
+    // These Ids might have died in the meantime, so they need to be normalized with `find_id`.
+    // It's `None` if `clear_dirty` has never been called. Then every e-class counts as dirty, and we don't need to track anything.
+    dirty: Option<HashSet<Id>>,
+
+    // Like `dirty`, but never cleared: stores for every living e-class the time of its last change.
+    // Time is measured by `clock`, which is incremented upon every change.
+    // This allows multiple consumers (like an Extractor) to each track their own changes.
+    // Dead e-classes are forgotten, but the e-classes using them are touched when their e-nodes are updated in `handle_pending`.
+    last_touched: HashMap<Id, usize>,
+
+    // The inverse of `last_touched`, so that the recent changes can be found quickly.
+    touch_log: BTreeMap<usize, Id>,
+    clock: usize,
+
     // TODO remove this if explanations are disabled.
     pub(crate) proof_registry: ProofRegistry,
 
     pub(crate) subst_method: Option<Box<dyn SubstMethod<L, N>>>,
+
+    // Counts all successful unions and newly created e-nodes so far.
+    // Rewrites use the difference of this before and after applying them.
+    pub(crate) changes: Changes,
 }
 
 impl<L: Language, N: Analysis<L>> EGraph<L, N> {
@@ -94,10 +121,17 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
             unionfind: Default::default(),
             classes: Default::default(),
             hashcons: Default::default(),
+            op_index: Default::default(),
             syn_hashcons: Default::default(),
             pending: Default::default(),
+            analysis_pending: Default::default(),
+            dirty: None,
+            last_touched: Default::default(),
+            touch_log: Default::default(),
+            clock: 0,
             proof_registry: ProofRegistry::default(),
             subst_method: Some(S::new_boxed()),
+            changes: Changes::default(),
         }
     }
 
@@ -116,6 +150,9 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
                        .collect()
     }
 
+    /// The analysis data of the e-class `i`, expressed in terms of the slots of `i`.
+    ///
+    /// If the analysis data mentions slots, you likely want [EGraph::analysis_data_applied] instead.
     pub fn analysis_data(&self, i: Id) -> &N {
         &self.classes[&self.find_id(i)].analysis_data
     }
@@ -124,6 +161,12 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         &mut self.classes.get_mut(&self.find_id(i)).unwrap().analysis_data
     }
 
+    /// The analysis data of `i`, with its slots renamed according to `i.m`.
+    pub fn analysis_data_applied(&self, i: &AppliedId) -> N {
+        let i = self.find_applied_id(i);
+        self.classes[&i.id].analysis_data.apply_slotmap(&i.m)
+    }
+
     // TODO For non-normalized inputs i, the slots in the output will definitely be wrong.
     // if x in enodes(i), then I'd expect x.slots() superset slots(i).
     pub fn enodes(&self, i: Id) -> HashSet<L> {
@@ -133,23 +176,36 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
 
     // Generates fresh slots for redundant slots.
     pub fn enodes_applied(&self, i: &AppliedId) -> HashSet<L> {
+        self.enodes_applied_filtered(i, |_| true)
+    }
+
+    // Renames the e-node `x`, which uses the slots of the e-class `i.id`, to the slots of `i`.
+    // Its private and redundant slots get fresh names.
+    pub(crate) fn enode_applied(&self, x: &L, i: &AppliedId) -> L {
+        // This is necessary, as i.slots() might collide with the private/redundant slots of our e-nodes.
+        let set: HashSet<_> = x.all_slot_occurences()
+                               .into_iter()
+                               .collect::<HashSet<_>>()
+                               .difference(&self.classes[&i.id].slots)
+                               .copied()
+                               .collect();
+        let x = x.refresh_slots(set);
+
+        let red = &x.slots() - &i.m.keys();
+        let fbij = SlotMap::bijection_from_fresh_to(&red);
+        let m = fbij.inverse().union(&i.m);
+        x.apply_slotmap(&m)
+    }
+
+    // Like enodes_applied, but only returns the e-nodes satisfying `f`.
+    // This allows us to skip the slot-refreshing for e-nodes that we are not interested in.
+    pub(crate) fn enodes_applied_filtered(&self, i: &AppliedId, f: impl Fn(&L) -> bool) -> HashSet<L> {
         let i = self.find_applied_id(i);
 
         let mut out = HashSet::default();
         for x in self.enodes(i.id) {
-            // This is necessary, as i.slots() might collide with the private/redundant slots of our e-nodes.
-            let set: HashSet<_> = x.all_slot_occurences()
-                                   .into_iter()
-                                   .collect::<HashSet<_>>()
-                                   .difference(&self.classes[&i.id].slots)
-                                   .copied()
-                                   .collect();
-            let x = x.refresh_slots(set);
-
-            let red = &x.slots() - &i.m.keys();
-            let fbij = SlotMap::bijection_from_fresh_to(&red);
-            let m = fbij.inverse().union(&i.m);
-            out.insert(x.apply_slotmap(&m));
+            if !f(&x) { continue; }
+            out.insert(self.enode_applied(&x, &i));
         }
 
         if CHECKS {
@@ -161,6 +217,84 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         out
     }
 
+    /// Returns all e-classes containing an e-node with the same operator as `n`, in ascending order.
+    ///
+    /// The slots and AppliedIds of `n` are ignored, only its operator matters.
+    pub fn classes_with_op(&self, n: &L) -> Vec<Id> {
+        let Some(ids) = self.op_index.get(&operator(n)) else { return Vec::new() };
+        let mut out: Vec<Id> = ids.keys().copied().collect();
+        out.sort();
+        out
+    }
+
+    /// Returns the e-classes that changed since the last call to [EGraph::clear_dirty],
+    /// together with their ancestors up to `depth` levels above them.
+    ///
+    /// An e-class changes, if it gets new e-nodes, new symmetries, fewer slots or new analysis data.
+    /// A pattern of depth `depth` can only have new matches rooted in these e-classes.
+    pub fn dirty_classes(&self, depth: usize) -> HashSet<Id> {
+        let Some(dirty) = &self.dirty else { return self.ids().into_iter().collect() };
+        let mut out: HashSet<Id> = dirty.iter().map(|i| self.find_id(*i)).collect();
+        let mut frontier: Vec<Id> = out.iter().copied().collect();
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for i in frontier {
+                for sh in &self.classes[&i].usages {
+                    let j = self.hashcons[sh];
+                    if out.insert(j) {
+                        next.push(j);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        out
+    }
+
+    /// Forgets about all changes so far, see [EGraph::dirty_classes].
+    ///
+    /// Until this is called for the first time, all e-classes are considered dirty.
+    pub fn clear_dirty(&mut self) {
+        self.dirty = Some(HashSet::default());
+    }
+
+    pub(crate) fn mark_dirty(&mut self, i: Id) {
+        if let Some(dirty) = &mut self.dirty {
+            dirty.insert(i);
+        }
+        self.touch(i);
+    }
+
+    // Records a change of `i` in `last_touched`, without marking it as dirty.
+    pub(crate) fn touch(&mut self, i: Id) {
+        if let Some(t) = self.last_touched.remove(&i) {
+            self.touch_log.remove(&t);
+        }
+        if !self.is_alive(i) { return; }
+
+        self.clock += 1;
+        self.last_touched.insert(i, self.clock);
+        self.touch_log.insert(self.clock, i);
+    }
+
+    // The current time, see `last_touched`.
+    pub(crate) fn clock(&self) -> usize {
+        self.clock
+    }
+
+    // The living e-classes that changed after time `t`.
+    pub(crate) fn touched_since(&self, t: usize) -> Vec<Id> {
+        let mut out: Vec<Id> = self.touch_log.range(t+1..).map(|(_, i)| *i).collect();
+        out.sort();
+        out
+    }
+
+    // Whether the e-class `i` contains an e-node with the operator `op`.
+    pub(crate) fn has_op(&self, i: Id, op: &L) -> bool {
+        let i = self.find_id(i);
+        self.op_index.get(op).map(|ids| ids.contains_key(&i)).unwrap_or(false)
+    }
+
     // number of enodes in the egraph.
     pub fn total_number_of_nodes(&self) -> usize {
         self.hashcons.len()
@@ -381,6 +515,14 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
     }
 }
 
+// The operator of an e-node: it forgets about the AppliedIds, and normalizes the slot names.
+// Two e-nodes have the same operator iff they could be matched by the same pattern e-node.
+pub(crate) fn operator<L: Language>(n: &L) -> L {
+    // As the node is nullified, it has no AppliedIds with slot args.
+    // Hence, its weak shape is already normalized.
+    nullify_app_ids(n).weak_shape().0
+}
+
 // {1,2} x {3} x {4,5} -> (1,3,4), (1,3,5), (2,3,4), (2,3,5)
 // TODO re-enable use<...> when it's stabilized.
 fn cartesian<'a, T>(input: &'a [Vec<T>]) -> impl Iterator<Item=Vec<&'a T>> /*+ use<'a, T>*/ + '_ {
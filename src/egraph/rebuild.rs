@@ -74,9 +74,13 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         }
 
         c.slots = cap.clone();
+        c.analysis_data = c.analysis_data.apply_slotmap(&SlotMap::identity(&cap));
         let generators = c.group.generators();
         let _ = c;
 
+        // The analysis data lost the redundant slots, so `modify` has to see it again.
+        self.analysis_pending.insert(id);
+
         let restrict_proven = |proven_perm: ProvenPerm| {
             proven_perm.check();
 
@@ -108,17 +112,29 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
 
     pub(crate) fn rebuild(&mut self) {
         if CHECKS { self.check(); }
-        while let Some(sh) = self.pending.iter().cloned().next() {
-            self.pending.remove(&sh);
-            self.handle_pending(sh);
+        loop {
+            while let Some(sh) = self.pending.iter().cloned().next() {
+                self.pending.remove(&sh);
+                self.handle_pending(sh);
+
+                if CHECKS { self.check(); }
+            }
 
-            if CHECKS { self.check(); }
+            // Analysis::modify may add e-nodes and union e-classes, and hence re-enter rebuild.
+            // That's why we only call it once all pending e-nodes are processed.
+            let Some(i) = self.analysis_pending.iter().copied().next() else { break };
+            self.analysis_pending.remove(&i);
+            let i = self.find_id(i);
+            N::modify(&mut AnalysisMut::new(self), i);
         }
     }
 
     fn handle_pending(&mut self, sh: L) {
         let i = self.hashcons[&sh];
 
+        // The e-node might refer to e-classes that died or shrank, so consumers of `touched_since` have to look at `i` again.
+        self.touch(i);
+
         /*
         let t = self.shape(&sh);
         if t.0 != sh {
@@ -175,15 +191,16 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
     }
 
     fn update_analysis(&mut self, sh: &L, i: Id) {
-        let v = N::make(self, sh);
+        // `make` expects the e-node to use the slots of the e-class.
+        let enode = sh.apply_slotmap(&self.classes[&i].nodes[sh].elem);
+        let v = N::make(&AnalysisView::new(self), &enode);
 
         let c = self.classes.get_mut(&i).unwrap();
-        let old = c.analysis_data.clone();
-        let new = N::merge(old.clone(), v);
-        c.analysis_data = new.clone();
+        let DidMerge(changed, _) = N::merge(&mut c.analysis_data, v);
 
-        if new != old {
+        if changed {
             self.touched_class(i);
+            self.analysis_pending.insert(i);
         }
     }
 
@@ -233,7 +250,12 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
                     proven_perm.check();
                 }
                 let grp = &mut self.classes.get_mut(&i).unwrap().group;
+                if grp.contains(&proven_perm.to_slotmap()) { continue; }
                 grp.add(proven_perm);
+
+                // A new symmetry can cause new matches, just like in `union_internal`.
+                self.merge_symmetric_analysis(i);
+                self.touched_class(i);
             }
         }
     }
@@ -248,6 +270,7 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
 
     // upon touching an e-class, you need to update all usages of it.
     pub(crate) fn touched_class(&mut self, i: Id) {
+        self.mark_dirty(i);
         for sh in &self.classes[&i].usages {
             self.pending.insert(sh.clone());
         }
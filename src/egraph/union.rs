@@ -26,6 +26,9 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
 
         let out = self.union_internal(&a, &b, proof);
         self.rebuild();
+        if out {
+            self.changes.unions += 1;
+        }
         out
     }
 
@@ -99,6 +102,7 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
 
             grp.add(proven_perm);
 
+            self.merge_symmetric_analysis(id);
             self.touched_class(id);
 
             true
@@ -137,15 +141,18 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
             assert_eq!(to.id, proof.r.id);
         }
 
-        let analysis_from = self.analysis_data(from.id).clone();
-        let analysis_to = self.analysis_data_mut(to.id);
-        *analysis_to = N::merge(analysis_from, analysis_to.clone());
-
         // from.m :: slots(from.id) -> X
         // to.m :: slots(to.id) -> X
         let map = to.m.compose_partial(&from.m.inverse());
         self.assert_ty(&map, &self.slots(to.id), &self.slots(from.id));
 
+        // The analysis data of `from` uses the slots of `from.id`, so we need to rename them to slots of `to.id`.
+        let analysis_from = self.analysis_data(from.id).apply_slotmap(&map.inverse());
+        let DidMerge(changed, _) = N::merge(self.analysis_data_mut(to.id), analysis_from);
+        if changed {
+            self.analysis_pending.insert(to.id);
+        }
+
         let app_id = self.mk_sem_applied_id(to.id, map.clone());
         let pai = ProvenAppliedId {
             elem: app_id,
@@ -225,4 +232,27 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         // touched because the class is now dead and no e-nodes should point to it.
         self.touched_class(from.id);
     }
+
+    // The analysis data has to be invariant under the self-symmetries of its e-class.
+    // So whenever the group grows, we merge the data with its permuted versions.
+    pub(in crate::egraph) fn merge_symmetric_analysis(&mut self, i: Id) {
+        let c = self.classes.get_mut(&i).unwrap();
+        let perms: Vec<SlotMap> = c.group.generators().into_iter().map(|p| p.to_slotmap()).collect();
+
+        let mut changed = false;
+        loop {
+            let mut changed_now = false;
+            for perm in &perms {
+                let permuted = c.analysis_data.apply_slotmap(perm);
+                let DidMerge(ch, _) = N::merge(&mut c.analysis_data, permuted);
+                changed_now |= ch;
+            }
+            if !changed_now { break }
+            changed = true;
+        }
+
+        if changed {
+            self.analysis_pending.insert(i);
+        }
+    }
 }
@@ -4,12 +4,31 @@ use std::marker::PhantomData;
 
 /// A cost function to guide extraction.
 ///
-/// If you want to use your e-graph analysis in your cost function, then your cost function should hold a reference to the e-graph.
+/// If you want to use your e-graph analysis in your cost function, implement [AnalysisCostFunction] instead.
 pub trait CostFunction<L: Language> {
     type Cost: Ord + Clone + Debug;
     fn cost<C>(&self, enode: &L, costs: C) -> Self::Cost where C: Fn(Id) -> Self::Cost;
 }
 
+/// A cost function that can look into the e-graph, and thus its analysis data.
+///
+/// The children of `enode` are [AppliedId]s, so their slot maps tell you which slots each child uses,
+/// and [EGraph::analysis_data_applied] gives you their analysis data in terms of these slots.
+///
+/// Every [CostFunction] is an [AnalysisCostFunction] as well.
+pub trait AnalysisCostFunction<L: Language, N: Analysis<L>> {
+    type Cost: Ord + Clone + Debug;
+    fn cost<C>(&self, eg: &EGraph<L, N>, enode: &L, costs: C) -> Self::Cost where C: Fn(Id) -> Self::Cost;
+}
+
+impl<L: Language, N: Analysis<L>, CF: CostFunction<L>> AnalysisCostFunction<L, N> for CF {
+    type Cost = CF::Cost;
+
+    fn cost<C>(&self, _eg: &EGraph<L, N>, enode: &L, costs: C) -> CF::Cost where C: Fn(Id) -> CF::Cost {
+        CostFunction::cost(self, enode, costs)
+    }
+}
+
 /// The 'default' [CostFunction]. It measures the size of the abstract syntax tree of the corresponding term.
 #[derive(Default)]
 pub struct AstSize;
@@ -0,0 +1,234 @@
+use crate::*;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A cost function for DAG extraction.
+///
+/// In contrast to a [CostFunction], it only gives the cost of the e-node itself, without its children.
+/// The cost of a DAG is the sum of the costs of its e-nodes, so every shared e-class is paid for once.
+pub trait DagCostFunction<L: Language> {
+    fn node_cost(&self, enode: &L) -> u64;
+}
+
+impl<L: Language> DagCostFunction<L> for AstSize {
+    fn node_cost(&self, _enode: &L) -> u64 { 1 }
+}
+
+/// The result of a DAG extraction, see [dag_extract].
+#[derive(Clone, Debug)]
+pub struct DagExtraction<L: Language> {
+    pub root: AppliedId,
+
+    /// The chosen e-node for every e-class that is needed by the extracted term.
+    /// The e-nodes use the slots of their e-class.
+    pub choices: HashMap<Id, L>,
+
+    /// The sum of the node costs of all choices.
+    pub cost: u64,
+}
+
+impl<L: Language> DagExtraction<L> {
+    /// Unfolds the chosen e-nodes into a term. Shared e-classes are duplicated here.
+    pub fn to_recexpr(&self) -> RecExpr<L> {
+        self.to_recexpr_impl(&self.root)
+    }
+
+    fn to_recexpr_impl(&self, i: &AppliedId) -> RecExpr<L> {
+        // `i.m` doesn't cover the redundant slots of the e-node.
+        let l = self.choices[&i.id].apply_slotmap_fresh(&i.m);
+        let children = l.applied_id_occurences().iter().map(|x| self.to_recexpr_impl(x)).collect();
+        RecExpr {
+            node: l,
+            children,
+        }
+    }
+}
+
+/// Greedily extracts a DAG for `i`, that pays for every shared e-class only once.
+///
+/// The e-classes choose their e-nodes in the order of their DAG cost, given the choices of the e-classes before them.
+/// This is a heuristic, use [dag_extract_exact] if you need the optimum.
+///
+/// Returns an error, if `i` has no finite term.
+pub fn dag_extract<L: Language, N: Analysis<L>, CF: DagCostFunction<L>>(i: AppliedId, eg: &EGraph<L, N>, cost_fn: &CF) -> Result<DagExtraction<L>, ExtractionError> {
+    let i = eg.find_applied_id(&i);
+    let nodes = class_nodes(eg);
+    let table = greedy(&nodes, cost_fn);
+    if !table.contains_key(&i.id) {
+        return Err(ExtractionError::Unextractable(i.id));
+    }
+    let (choices, cost) = prune(i.id, &table, cost_fn);
+    Ok(DagExtraction { root: i, choices, cost })
+}
+
+/// Extracts a DAG for `i` with minimal cost.
+///
+/// This is a branch-and-bound search over the choices of all reachable e-classes, bounded by the result of [dag_extract].
+/// It takes exponential time in the worst case, so it's only feasible for small e-graphs.
+///
+/// Returns an error, if `i` has no finite term.
+pub fn dag_extract_exact<L: Language, N: Analysis<L>, CF: DagCostFunction<L>>(i: AppliedId, eg: &EGraph<L, N>, cost_fn: &CF) -> Result<DagExtraction<L>, ExtractionError> {
+    let i = eg.find_applied_id(&i);
+    let nodes = class_nodes(eg);
+    let table = greedy(&nodes, cost_fn);
+    if !table.contains_key(&i.id) {
+        return Err(ExtractionError::Unextractable(i.id));
+    }
+
+    let min_cost: HashMap<Id, u64> = nodes.iter()
+        .map(|(c, ns)| (*c, ns.iter().map(|n| cost_fn.node_cost(n)).min().unwrap_or(u64::MAX)))
+        .collect();
+
+    let mut bb = BranchAndBound {
+        nodes: &nodes,
+        cost_fn,
+        min_cost,
+        best: prune(i.id, &table, cost_fn),
+        chosen: HashMap::default(),
+        pending: vec![i.id],
+    };
+    bb.run(0);
+
+    let (choices, cost) = bb.best;
+    Ok(DagExtraction { root: i, choices, cost })
+}
+
+fn children<L: Language>(n: &L) -> impl Iterator<Item=Id> {
+    n.applied_id_occurences().into_iter().map(|x| x.id)
+}
+
+// Only keeps the choices reachable from `root`, and computes their cost.
+fn prune<L: Language, CF: DagCostFunction<L>>(root: Id, choices: &HashMap<Id, L>, cost_fn: &CF) -> (HashMap<Id, L>, u64) {
+    let mut out = HashMap::default();
+    let mut cost: u64 = 0;
+    let mut stack = vec![root];
+    while let Some(c) = stack.pop() {
+        if out.contains_key(&c) { continue; }
+        let n = &choices[&c];
+        cost = cost.saturating_add(cost_fn.node_cost(n));
+        stack.extend(children(n));
+        out.insert(c, n.clone());
+    }
+    (out, cost)
+}
+
+// The DAG cost of `n`, if its children use the e-nodes from `choices`.
+fn dag_cost<L: Language, CF: DagCostFunction<L>>(n: &L, choices: &HashMap<Id, L>, cost_fn: &CF) -> u64 {
+    let mut visited: HashSet<Id> = HashSet::default();
+    let mut cost = cost_fn.node_cost(n);
+    let mut stack: Vec<Id> = children(n).collect();
+    while let Some(c) = stack.pop() {
+        if !visited.insert(c) { continue; }
+        let m = &choices[&c];
+        cost = cost.saturating_add(cost_fn.node_cost(m));
+        stack.extend(children(m));
+    }
+    cost
+}
+
+// Chooses an e-node for every e-class, in a single table.
+// Like in `Extractor::new`, an e-node only becomes a candidate once all of its children have been chosen, so the choices are acyclic.
+// The candidates are handled in the order of their DAG cost, and the first candidate of each e-class wins.
+fn greedy<L: Language, CF: DagCostFunction<L>>(nodes: &HashMap<Id, Vec<L>>, cost_fn: &CF) -> HashMap<Id, L> {
+    // the e-nodes using each e-class, given by their e-class and their index in `nodes`.
+    let mut parents: HashMap<Id, Vec<(Id, usize)>> = HashMap::default();
+
+    // the number of distinct children of each e-node that have not been chosen yet.
+    let mut missing: HashMap<(Id, usize), usize> = HashMap::default();
+
+    let mut queue: BinaryHeap<Reverse<(u64, Id, usize)>> = BinaryHeap::new();
+    let mut table: HashMap<Id, L> = HashMap::default();
+
+    let mut ids: Vec<Id> = nodes.keys().copied().collect();
+    ids.sort();
+    for &c in &ids {
+        for (k, n) in nodes[&c].iter().enumerate() {
+            let mut ch: Vec<Id> = children(n).collect();
+            ch.sort();
+            ch.dedup();
+            for x in &ch {
+                parents.entry(*x).or_default().push((c, k));
+            }
+            missing.insert((c, k), ch.len());
+            if ch.is_empty() {
+                queue.push(Reverse((cost_fn.node_cost(n), c, k)));
+            }
+        }
+    }
+
+    while let Some(Reverse((_, c, k))) = queue.pop() {
+        if table.contains_key(&c) { continue; }
+        table.insert(c, nodes[&c][k].clone());
+
+        for &(p, j) in parents.get(&c).map(|v| &v[..]).unwrap_or(&[]) {
+            let m = missing.get_mut(&(p, j)).unwrap();
+            *m -= 1;
+            if *m == 0 && !table.contains_key(&p) {
+                queue.push(Reverse((dag_cost(&nodes[&p][j], &table, cost_fn), p, j)));
+            }
+        }
+    }
+    table
+}
+
+struct BranchAndBound<'a, L: Language, CF: DagCostFunction<L>> {
+    nodes: &'a HashMap<Id, Vec<L>>,
+    cost_fn: &'a CF,
+
+    // the cheapest e-node of each e-class, used as a lower bound.
+    min_cost: HashMap<Id, u64>,
+
+    best: (HashMap<Id, L>, u64),
+    chosen: HashMap<Id, L>,
+
+    // e-classes that are needed, but not chosen yet.
+    pending: Vec<Id>,
+}
+
+impl<'a, L: Language, CF: DagCostFunction<L>> BranchAndBound<'a, L, CF> {
+    fn run(&mut self, cost: u64) {
+        let bound = self.pending.iter().fold(cost, |acc, c| acc.saturating_add(self.min_cost[c]));
+        if bound >= self.best.1 { return; }
+
+        let Some(c) = self.pending.pop() else {
+            self.best = (self.chosen.clone(), cost);
+            return;
+        };
+
+        let mut candidates: Vec<&L> = self.nodes[&c].iter().collect();
+        candidates.sort_by_key(|n| self.cost_fn.node_cost(n));
+        for n in candidates {
+            if children(n).any(|x| self.reaches(x, c)) { continue; }
+
+            self.chosen.insert(c, n.clone());
+            let old_len = self.pending.len();
+            for x in children(n) {
+                if !self.chosen.contains_key(&x) && !self.pending.contains(&x) {
+                    self.pending.push(x);
+                }
+            }
+
+            self.run(cost.saturating_add(self.cost_fn.node_cost(n)));
+
+            self.pending.truncate(old_len);
+            self.chosen.remove(&c);
+        }
+
+        self.pending.push(c);
+    }
+
+    // whether `target` is reachable from `i` using only chosen e-nodes.
+    fn reaches(&self, i: Id, target: Id) -> bool {
+        let mut stack = vec![i];
+        let mut visited = HashSet::default();
+        while let Some(x) = stack.pop() {
+            if x == target { return true; }
+            if !visited.insert(x) { continue; }
+            if let Some(n) = self.chosen.get(&x) {
+                stack.extend(children(n));
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,122 @@
+use crate::*;
+
+/// Returns every term of `i` consisting of at most `max_size` e-nodes, sorted by size.
+///
+/// Terms that only differ in the names of their bound slots are returned once.
+/// The free slots of the terms are the slots of `i`.
+///
+/// The number of terms can grow exponentially in `max_size`, so this is mostly useful for testing.
+pub fn enumerate_terms<L: Language, N: Analysis<L>>(i: AppliedId, eg: &EGraph<L, N>, max_size: usize) -> Vec<RecExpr<L>> {
+    let mut en = Enumerator { eg, memo: HashMap::default() };
+    let mut terms = en.terms(&i, max_size);
+    terms.sort_by_key(|(_, size)| *size);
+    terms.into_iter().map(|(re, _)| re).collect()
+}
+
+/// Counts the terms consisting of at most `max_size` e-nodes, for every e-class.
+///
+/// In contrast to [enumerate_terms], the terms are never built, so this is way cheaper.
+/// The counts saturate at `u64::MAX`.
+///
+/// Both functions consider one term per combination of e-nodes, so the count of an e-class is the length of its [enumerate_terms].
+/// In particular, the self-symmetries of an e-class don't create additional terms:
+/// If `(add (var $x) (var $y))` and `(add (var $y) (var $x))` are in the same e-class, they are a single e-node, and hence count once.
+pub fn count_terms<L: Language, N: Analysis<L>>(eg: &EGraph<L, N>, max_size: usize) -> HashMap<Id, u64> {
+    let exact = exact_counts(&class_nodes(eg), max_size);
+    exact.into_iter()
+         .map(|(c, v)| (c, v.into_iter().fold(0, u64::saturating_add)))
+         .collect()
+}
+
+// `exact[c][s]` is the number of terms of the e-class `c` with exactly `s` e-nodes.
+pub(crate) fn exact_counts<L: Language>(nodes: &HashMap<Id, Vec<L>>, max_size: usize) -> HashMap<Id, Vec<u64>> {
+    let mut exact: HashMap<Id, Vec<u64>> = nodes.keys().map(|c| (*c, vec![0; max_size + 1])).collect();
+
+    // A term of size `s` only consists of smaller terms, so the sizes can be handled in increasing order.
+    for s in 1..=max_size {
+        for (c, ns) in nodes {
+            let mut count: u64 = 0;
+            for n in ns {
+                count = count.saturating_add(count_combinations(n, &exact, s - 1));
+            }
+            exact.get_mut(c).unwrap()[s] = count;
+        }
+    }
+    exact
+}
+
+// the number of ways to fill the children of `n` with terms, using exactly `budget` e-nodes in total.
+pub(crate) fn count_combinations<L: Language>(n: &L, exact: &HashMap<Id, Vec<u64>>, budget: usize) -> u64 {
+    // `ways[s]` is the number of ways to fill the children handled so far, using exactly `s` e-nodes.
+    let mut ways: Vec<u64> = vec![0; budget + 1];
+    ways[0] = 1;
+    for c in n.applied_id_occurences() {
+        let child = &exact[&c.id];
+        let mut next = vec![0; budget + 1];
+        for (s1, w) in ways.iter().enumerate() {
+            if *w == 0 { continue; }
+            for s2 in 1..=(budget - s1) {
+                next[s1 + s2] = w.saturating_mul(child[s2]).saturating_add(next[s1 + s2]);
+            }
+        }
+        ways = next;
+    }
+    ways[budget]
+}
+
+struct Enumerator<'a, L: Language, N: Analysis<L>> {
+    eg: &'a EGraph<L, N>,
+
+    // the terms of an AppliedId for a given budget.
+    memo: HashMap<(AppliedId, usize), Vec<SizedTerm<L>>>,
+}
+
+// a term together with its number of e-nodes.
+type SizedTerm<L> = (RecExpr<L>, usize);
+
+impl<'a, L: Language, N: Analysis<L>> Enumerator<'a, L, N> {
+    fn terms(&mut self, a: &AppliedId, budget: usize) -> Vec<SizedTerm<L>> {
+        if budget == 0 { return Vec::new(); }
+
+        let key = (a.clone(), budget);
+        if let Some(x) = self.memo.get(&key) {
+            return x.clone();
+        }
+
+        let mut out = Vec::new();
+        let mut seen: HashSet<AlphaRecExpr<L>> = HashSet::default();
+        for n in self.eg.enodes_applied(a) {
+            let children = n.applied_id_occurences();
+            if children.len() + 1 > budget { continue; }
+
+            // the combinations of terms for the children handled so far, together with their size (including `n`).
+            let mut partial: Vec<(Vec<RecExpr<L>>, usize)> = vec![(Vec::new(), 1)];
+            for (k, c) in children.iter().enumerate() {
+                // Each of the remaining children needs at least one e-node.
+                let rest = children.len() - k - 1;
+                let child_terms = self.terms(c, budget - (1 + k) - rest);
+
+                let mut next = Vec::new();
+                for (ch, used) in &partial {
+                    for (t, s) in &child_terms {
+                        if used + s + rest > budget { continue; }
+                        let mut ch = ch.clone();
+                        ch.push(t.clone());
+                        next.push((ch, used + s));
+                    }
+                }
+                partial = next;
+            }
+
+            for (ch, size) in partial {
+                let re = RecExpr { node: n.clone(), children: ch };
+                if seen.insert(AlphaRecExpr::new(&re)) {
+                    out.push((re, size));
+                }
+            }
+        }
+
+        self.memo.insert(key, out.clone());
+        out
+    }
+}
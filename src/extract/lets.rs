@@ -0,0 +1,158 @@
+use crate::*;
+
+impl<L: Language, CF, C: Ord + Clone + Debug> Extractor<L, CF, C> {
+    /// Like [Extractor::extract], but every term that is used multiple times is only extracted once.
+    ///
+    /// A shared term `t` is bound using `mk_let(x, t, body)`, and its occurences in `body` are replaced by `mk_var(x)`, for some fresh slot `x`.
+    /// The let is put at the lowest position above all occurences of `t`,
+    /// but it never leaves a binder that binds one of the free slots of `t`.
+    /// In that case, `t` is bound separately in each child of the binder that uses it, and only if it occurs more than once there.
+    ///
+    /// Terms without children (like variables) are never shared.
+    ///
+    /// Returns an error, if `i` has no finite term.
+    pub fn extract_with_lets<N: Analysis<L>>(&self, i: AppliedId, eg: &EGraph<L, N>, mk_let: impl Fn(Slot, RecExpr<L>, RecExpr<L>) -> RecExpr<L>, mk_var: impl Fn(Slot) -> RecExpr<L>) -> Result<RecExpr<L>, ExtractionError> {
+        let root = eg.find_applied_id(&i);
+
+        let mut li = LetInsertion {
+            nodes: HashMap::default(),
+            indegree: HashMap::default(),
+            order: HashMap::default(),
+            vars: HashMap::default(),
+            mk_let: &mk_let,
+            mk_var: &mk_var,
+        };
+        self.visit(&root, eg, &mut li)?;
+
+        // We reverse the post-order, so that every term comes before the terms that it uses.
+        let n = li.order.len();
+        for v in li.order.values_mut() {
+            *v = n - *v;
+        }
+
+        for (a, d) in &li.indegree {
+            if *d >= 2 && !li.nodes[a].1.is_empty() {
+                li.vars.insert(a.clone(), Slot::fresh());
+            }
+        }
+
+        let (re, mut floating) = li.term(&root);
+        Ok(li.place(re, &mut floating, |_, _| true))
+    }
+
+    // collects the chosen e-nodes of all AppliedIds reachable from `a`.
+    fn visit<N: Analysis<L>, F, V>(&self, a: &AppliedId, eg: &EGraph<L, N>, li: &mut LetInsertion<L, F, V>) -> Result<(), ExtractionError> {
+        let Some(x) = self.map.get(&a.id) else { return Err(ExtractionError::Unextractable(a.id)) };
+
+        // Redundant slots of the e-node get fresh names, as they don't occur in `a`.
+        let l = x.0.apply_slotmap_fresh(&a.m);
+        let children: Vec<AppliedId> = l.applied_id_occurences().iter().map(|c| eg.find_applied_id(c)).collect();
+        li.nodes.insert(a.clone(), (l, children.clone()));
+
+        for c in children {
+            *li.indegree.entry(c.clone()).or_default() += 1;
+            if !li.nodes.contains_key(&c) {
+                self.visit(&c, eg, li)?;
+            }
+        }
+
+        let n = li.order.len();
+        li.order.insert(a.clone(), n);
+        Ok(())
+    }
+}
+
+// The shared terms that are used, but not yet bound, together with their number of occurences.
+type Floating = HashMap<AppliedId, usize>;
+
+struct LetInsertion<'a, L: Language, F, V> {
+    // the chosen e-node of each AppliedId, together with its normalized children.
+    nodes: HashMap<AppliedId, (L, Vec<AppliedId>)>,
+
+    // how often each AppliedId is used by the chosen e-nodes.
+    indegree: HashMap<AppliedId, usize>,
+
+    // a topological order, in which every AppliedId comes before its children.
+    order: HashMap<AppliedId, usize>,
+
+    // the let-bound slot of each shared AppliedId.
+    vars: HashMap<AppliedId, Slot>,
+
+    mk_let: &'a F,
+    mk_var: &'a V,
+}
+
+impl<'a, L: Language, F: Fn(Slot, RecExpr<L>, RecExpr<L>) -> RecExpr<L>, V: Fn(Slot) -> RecExpr<L>> LetInsertion<'a, L, F, V> {
+    fn use_term(&self, a: &AppliedId) -> (RecExpr<L>, Floating) {
+        match self.vars.get(a) {
+            Some(x) => {
+                let mut floating = Floating::default();
+                floating.insert(a.clone(), 1);
+                ((self.mk_var)(*x), floating)
+            },
+            None => self.term(a),
+        }
+    }
+
+    // builds the term of `a`, even if `a` is shared.
+    fn term(&self, a: &AppliedId) -> (RecExpr<L>, Floating) {
+        let (l, child_ids) = &self.nodes[a];
+        let private = l.private_slots();
+
+        let mut floating = Floating::default();
+        let mut children = Vec::new();
+        for c in child_ids {
+            let (re, mut fl) = self.use_term(c);
+
+            // Shared terms using a slot bound by `l` have to be bound below `l`.
+            let re = self.place(re, &mut fl, |b, _| !b.slots().is_disjoint(&private));
+            for (b, n) in fl {
+                *floating.entry(b).or_default() += n;
+            }
+            children.push(re);
+        }
+
+        let re = RecExpr {
+            node: l.clone(),
+            children,
+        };
+
+        // Shared terms whose occurences are all below `l` are bound here.
+        let re = self.place(re, &mut floating, |b, n| n >= self.indegree[b]);
+        (re, floating)
+    }
+
+    // binds the floating terms satisfying `pred` around `re`.
+    // The definitions of these terms might use further shared terms, which are handled the same way.
+    fn place(&self, mut re: RecExpr<L>, floating: &mut Floating, pred: impl Fn(&AppliedId, usize) -> bool) -> RecExpr<L> {
+        loop {
+            // Terms that are used by other terms are bound further outside, so we start with the first term in `order`.
+            let next = floating.iter()
+                .filter(|(b, n)| pred(b, **n))
+                .map(|(b, _)| b)
+                .min_by_key(|b| self.order[*b])
+                .cloned();
+            let Some(b) = next else { return re };
+
+            let n = floating.remove(&b).unwrap();
+            let (def, fl) = self.term(&b);
+            for (c, n) in fl {
+                *floating.entry(c).or_default() += n;
+            }
+            let x = self.vars[&b];
+
+            // A term that is shared by several instances of the same binder might be used only once in this instance.
+            // Such a term, or a let that directly returns its variable, is not worth a let.
+            re = if n == 1 { replace(re, &(self.mk_var)(x), &def) } else { (self.mk_let)(x, def, re) };
+        }
+    }
+}
+
+// replaces the occurences of `x` in `re` by `t`.
+fn replace<L: Language>(re: RecExpr<L>, x: &RecExpr<L>, t: &RecExpr<L>) -> RecExpr<L> {
+    if re == *x { return t.clone(); }
+    RecExpr {
+        node: re.node,
+        children: re.children.into_iter().map(|c| replace(c, x, t)).collect(),
+    }
+}
@@ -6,76 +6,216 @@ pub use cost::*;
 mod with_ord;
 pub use with_ord::*;
 
+mod dag;
+pub use dag::*;
+
+mod top_k;
+pub use top_k::*;
+
+mod lets;
+
+mod pareto;
+pub use pareto::*;
+
+mod sketch;
+pub use sketch::*;
+
+mod enumerate;
+pub use enumerate::*;
+
+mod sample;
+pub use sample::*;
+
+#[cfg(test)]
+mod tst;
+
 use std::collections::BinaryHeap;
 
 /// An object used for quickly extracting terms (i.e. [RecExpr]s) using a given [CostFunction].
 ///
 /// Creating an Extractor will setup an extraction-table which then allows you to extract terms from many e-classes efficiently.
 /// It is most useful when doing "bulk" extractions for many classes.
-pub struct Extractor<L: Language, CF: CostFunction<L>> {
-    pub(crate) map: HashMap<Id, WithOrdRev<L, CF::Cost>>,
+///
+/// `C` is the cost type of the cost function. It only needs to be given for an [AnalysisCostFunction] that isn't a [CostFunction].
+pub struct Extractor<L: Language, CF, C: Ord = <CF as CostFunction<L>>::Cost> {
+    pub(crate) map: HashMap<Id, WithOrdRev<L, C>>,
+
+    // for each e-class, the e-classes whose entry in `map` uses it.
+    parents: HashMap<Id, HashSet<Id>>,
+
+    // the e-classes that the worklist never reached, sorted.
+    unextractable: Vec<Id>,
+
+    cost_fn: CF,
+
+    // the time of the e-graph (see `EGraph::clock`) when this Extractor was last refreshed.
+    clock: usize,
+}
+
+/// The error of [Extractor::try_extract].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractionError {
+    /// The e-class has no finite term, or it was created after the [Extractor] was last refreshed.
+    Unextractable(Id),
 }
 
-impl<L: Language, CF: CostFunction<L>> Extractor<L, CF> {
-    pub fn new<N: Analysis<L>>(eg: &EGraph<L, N>, cost_fn: CF) -> Self {
-        eg.check();
+impl<L: Language, CF, C: Ord + Clone + Debug> Extractor<L, CF, C> {
+    pub fn new<N: Analysis<L>>(eg: &EGraph<L, N>, cost_fn: CF) -> Self where CF: AnalysisCostFunction<L, N, Cost=C> {
+        let mut ex = Extractor {
+            map: HashMap::default(),
+            parents: HashMap::default(),
+            unextractable: Vec::new(),
+            cost_fn,
+            clock: eg.clock(),
+        };
+        ex.recompute(eg, eg.ids());
+        ex
+    }
+
+    /// Updates the extraction-table after the e-graph changed.
+    ///
+    /// Only the e-classes that changed since the last refresh (and the e-classes using them) are recomputed.
+    /// This assumes that the cost of an e-node can only decrease, when the costs of its children decrease.
+    pub fn refresh<N: Analysis<L>>(&mut self, eg: &EGraph<L, N>) where CF: AnalysisCostFunction<L, N, Cost=C> {
+        let touched = eg.touched_since(self.clock);
+        self.clock = eg.clock();
+        self.recompute(eg, touched);
+    }
+
+    // recomputes the e-classes `touched`, and propagates the changes upwards.
+    fn recompute<N: Analysis<L>>(&mut self, eg: &EGraph<L, N>, touched: Vec<Id>) where CF: AnalysisCostFunction<L, N, Cost=C> {
+        if CHECKS {
+            eg.check();
+        }
 
         // all the L in `map` and `queue` have to be
         // - in "normal-form", i.e. calling lookup on them yields an identity AppliedId.
         // - every internal slot needs to be refreshed.
 
+        let cost_fn = &self.cost_fn;
+
         // maps eclass id to their optimal RecExpr.
-        let mut map: HashMap<Id, WithOrdRev<L, CF::Cost>> = HashMap::default();
-        let mut queue: BinaryHeap<WithOrdRev<L, CF::Cost>> = BinaryHeap::new();
+        let map = &mut self.map;
+        let parents = &mut self.parents;
+        let mut queue: BinaryHeap<WithOrdRev<L, C>> = BinaryHeap::new();
 
-        for id in eg.ids() {
+        // The entries of the touched e-classes might use outdated slots, or refer to e-classes that died or shrank.
+        // Entries that are built from invalid entries are invalid too.
+        let touched_set: HashSet<Id> = touched.iter().copied().collect();
+        let mut invalid: Vec<Id> = touched.iter().copied().filter(|i| map.contains_key(i)).collect();
+        let mut removed: HashSet<Id> = HashSet::default();
+        while let Some(i) = invalid.pop() {
+            let Some(x) = map.remove(&i) else { continue };
+            removed.insert(i);
+            for c in x.0.applied_id_occurences() {
+                unlink(parents, c.id, i);
+
+                // Dead e-classes are never touched again, so we drop their entries here.
+                if eg.find_id(c.id) != c.id {
+                    invalid.push(c.id);
+                }
+            }
+            if let Some(ps) = parents.remove(&i) {
+                invalid.extend(ps);
+            }
+        }
+
+        let mut touched = touched;
+        touched.extend(removed.into_iter().filter(|i| !touched_set.contains(i) && eg.find_id(*i) == *i));
+        touched.sort();
+
+        for &id in &touched {
+            if eg.find_id(id) != id { continue; }
             for x in eg.enodes(id) {
-                if x.applied_id_occurences().is_empty() {
+                if x.applied_id_occurences().iter().all(|i| map.contains_key(&i.id)) {
                     let x = eg.class_nf(&x);
-                    let c = cost_fn.cost(&x, |_| panic!());
+                    let c = cost_fn.cost(eg, &x, |i| map[&i].1.clone());
                     queue.push(WithOrdRev(x, c));
                 }
             }
         }
 
+        // The e-classes whose entry is final.
+        // As the costs popped from the queue never decrease, the first entry we pop for an e-class is optimal.
+        let mut done: HashSet<Id> = HashSet::default();
+
         while let Some(WithOrdRev(enode, c)) = queue.pop() {
             let i = eg.lookup(&enode).unwrap();
-            if map.contains_key(&i.id) {
+            if !done.insert(i.id) {
                 continue;
             }
-            map.insert(i.id, WithOrdRev(enode, c));
+
+            // Entries from previous refreshes are still valid, and might be cheaper.
+            if map.get(&i.id).map(|old| old.1 <= c).unwrap_or(false) {
+                continue;
+            }
+            for x in enode.applied_id_occurences() {
+                parents.entry(x.id).or_default().insert(i.id);
+            }
+            if let Some(old) = map.insert(i.id, WithOrdRev(enode, c)) {
+                for x in old.0.applied_id_occurences() {
+                    if !map[&i.id].0.applied_id_occurences().iter().any(|y| y.id == x.id) {
+                        unlink(parents, x.id, i.id);
+                    }
+                }
+            }
 
             for x in eg.usages(i.id).clone() {
                 if x.applied_id_occurences().iter().all(|i| map.contains_key(&i.id)) {
-                    if eg.lookup(&x).map(|i| map.contains_key(&i.id)).unwrap_or(false) {
+                    if eg.lookup(&x).map(|i| done.contains(&i.id)).unwrap_or(false) {
                         continue;
                     }
                     let x = eg.class_nf(&x);
-                    let c = cost_fn.cost(&x, |i| map[&i].1.clone());
+                    let c = cost_fn.cost(eg, &x, |i| map[&i].1.clone());
                     queue.push(WithOrdRev(x, c));
                 }
             }
         }
 
-        Self { map }
+        // Only the e-classes that were touched or lost their entry can have become unextractable.
+        let unextractable = &mut self.unextractable;
+        unextractable.retain(|i| eg.find_id(*i) == *i && !map.contains_key(i));
+        for i in touched {
+            if eg.find_id(i) == i && !map.contains_key(&i) {
+                unextractable.push(i);
+            }
+        }
+        unextractable.sort();
+        unextractable.dedup();
     }
 
+    /// Panics if `i` has no finite term, see [Extractor::try_extract].
     pub fn extract<N: Analysis<L>>(&self, i: AppliedId, eg: &EGraph<L, N>) -> RecExpr<L> {
+        match self.try_extract(i, eg) {
+            Ok(re) => re,
+            Err(e) => panic!("Extractor::extract: {e:?}"),
+        }
+    }
+
+    pub fn try_extract<N: Analysis<L>>(&self, i: AppliedId, eg: &EGraph<L, N>) -> Result<RecExpr<L>, ExtractionError> {
         let i = eg.find_applied_id(&i);
 
         let mut children = Vec::new();
 
         // do I need to refresh some slots here?
-        let l = self.map[&i.id].0.apply_slotmap(&i.m);
+        let Some(x) = self.map.get(&i.id) else { return Err(ExtractionError::Unextractable(i.id)) };
+        let l = x.0.apply_slotmap(&i.m);
         for child in l.applied_id_occurences() {
-            let n = self.extract(child, eg);
+            let n = self.try_extract(child, eg)?;
             children.push(n);
         }
 
-        RecExpr {
+        Ok(RecExpr {
             node: l,
             children,
-        }
+        })
+    }
+
+    /// All e-classes without a finite term, sorted by their [Id].
+    ///
+    /// These e-classes only consist of e-nodes which (transitively) depend on themselves.
+    pub fn unextractable(&self) -> &[Id] {
+        &self.unextractable
     }
 }
 
@@ -84,7 +224,56 @@ pub fn ast_size_extract<L: Language, N: Analysis<L>>(i: AppliedId, eg: &EGraph<L
 }
 
 // `i` is not allowed to have free variables, hence prefer `Id` over `AppliedId`.
-pub fn extract<L: Language, N: Analysis<L>, CF: CostFunction<L> + Default>(i: AppliedId, eg: &EGraph<L, N>) -> RecExpr<L> {
+pub fn extract<L: Language, N: Analysis<L>, CF: AnalysisCostFunction<L, N> + Default>(i: AppliedId, eg: &EGraph<L, N>) -> RecExpr<L> {
     let cost_fn = CF::default();
-    Extractor::<L, CF>::new(eg, cost_fn).extract(i, eg)
+    Extractor::new(eg, cost_fn).extract(i, eg)
+}
+
+/// Like [extract], but returns an error instead of panicking, if `i` has no finite term.
+pub fn try_extract<L: Language, N: Analysis<L>, CF: AnalysisCostFunction<L, N> + Default>(i: AppliedId, eg: &EGraph<L, N>) -> Result<RecExpr<L>, ExtractionError> {
+    let cost_fn = CF::default();
+    Extractor::new(eg, cost_fn).try_extract(i, eg)
+}
+
+// removes `p` from the parents of `c`.
+fn unlink(parents: &mut HashMap<Id, HashSet<Id>>, c: Id, p: Id) {
+    if let Some(ps) = parents.get_mut(&c) {
+        ps.remove(&p);
+        if ps.is_empty() {
+            parents.remove(&c);
+        }
+    }
+}
+
+// The e-nodes of each e-class, in terms of the slots of their e-class.
+// Redundant slots get fresh names, as the e-class can't tell us about them.
+pub(crate) fn class_nodes<L: Language, N: Analysis<L>>(eg: &EGraph<L, N>) -> HashMap<Id, Vec<L>> {
+    let mut out = HashMap::default();
+    for id in eg.ids() {
+        let i = AppliedId::new(id, SlotMap::identity(&eg.slots(id)));
+        let v: Vec<L> = eg.enodes_applied(&i).into_iter().collect();
+        out.insert(id, v);
+    }
+    out
+}
+
+/// Gives the costs of the children of `n` to a cost function, where the k-th child of `n` costs `costs[k]`.
+///
+/// If an e-node uses the same e-class multiple times, each of these occurences may get a different term.
+/// As a cost function asks for the costs of e-classes, such an e-class answers with the costs of its occurences in turn.
+/// So the cost function is expected to ask for the costs of the children in the order of [Language::applied_id_occurences].
+pub(crate) fn occurence_costs<'a, L: Language, C: Clone>(n: &L, costs: &'a [C]) -> impl Fn(Id) -> C + 'a {
+    let mut positions: HashMap<Id, Vec<usize>> = HashMap::default();
+    for (k, x) in n.applied_id_occurences().into_iter().enumerate() {
+        positions.entry(x.id).or_default().push(k);
+    }
+    let asked: std::cell::RefCell<HashMap<Id, usize>> = Default::default();
+    move |i| {
+        let ps = &positions[&i];
+        let mut asked = asked.borrow_mut();
+        let k = asked.entry(i).or_default();
+        let p = ps[*k % ps.len()];
+        *k += 1;
+        costs[p].clone()
+    }
 }
@@ -0,0 +1,167 @@
+use crate::*;
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+/// A cost function for [ParetoExtractor].
+///
+/// In contrast to a [CostFunction], the costs are only partially ordered.
+/// Two costs that are incomparable are both kept, as neither of them is better than the other.
+pub trait ParetoCostFunction<L: Language> {
+    type Cost: PartialOrd + Clone + Debug;
+    fn cost<C>(&self, enode: &L, costs: C) -> Self::Cost where C: Fn(Id) -> Self::Cost;
+}
+
+/// A cost consisting of two metrics, which is ordered component-wise.
+///
+/// `ParetoPair(a, b) <= ParetoPair(c, d)` holds iff `a <= c` and `b <= d`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ParetoPair<A, B>(pub A, pub B);
+
+impl<A: PartialOrd, B: PartialOrd> PartialOrd for ParetoPair<A, B> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.0.partial_cmp(&other.0)?, self.1.partial_cmp(&other.1)?) {
+            (Ordering::Equal, o) | (o, Ordering::Equal) => Some(o),
+            (o1, o2) if o1 == o2 => Some(o1),
+            _ => None,
+        }
+    }
+}
+
+/// Like an [Extractor], but for a [ParetoCostFunction].
+///
+/// For every e-class, it keeps the Pareto frontier: all terms whose cost is not dominated by the cost of another term.
+///
+/// An e-node that uses the same e-class multiple times may get a different term for each occurence, see `occurence_costs` for how they are costed.
+pub struct ParetoExtractor<L: Language, CF: ParetoCostFunction<L>> {
+    // every entry that was ever on the frontier of an e-class, as other entries might still pick it.
+    entries: HashMap<Id, Vec<Entry<L, CF::Cost>>>,
+
+    // the indices of the entries (in `entries`) that are on the Pareto frontier of each e-class.
+    frontier: HashMap<Id, Vec<usize>>,
+}
+
+struct Entry<L: Language, C> {
+    // the chosen e-node, in "normal-form" like in an Extractor.
+    enode: L,
+
+    // for each child of `enode`, the index of its chosen entry.
+    picks: Vec<usize>,
+
+    cost: C,
+}
+
+impl<L: Language, CF: ParetoCostFunction<L>> ParetoExtractor<L, CF> {
+    pub fn new<N: Analysis<L>>(eg: &EGraph<L, N>, cost_fn: CF) -> Self {
+        if CHECKS {
+            eg.check();
+        }
+
+        let mut pe = Self {
+            entries: HashMap::default(),
+            frontier: HashMap::default(),
+        };
+
+        // the new frontier entries, whose users have to be recomputed.
+        let mut worklist: VecDeque<(Id, usize)> = VecDeque::new();
+
+        let mut ids = eg.ids();
+        ids.sort();
+        for id in ids {
+            for x in eg.enodes(id) {
+                if x.applied_id_occurences().is_empty() {
+                    let x = eg.class_nf(&x);
+                    let cost = cost_fn.cost(&x, |_| panic!());
+                    if let Some(e) = pe.insert(id, Entry { enode: x, picks: Vec::new(), cost }) {
+                        worklist.push_back((id, e));
+                    }
+                }
+            }
+        }
+
+        // Like in `Extractor::new`, a new entry of an e-class is combined with the frontiers of the other children of its usages.
+        // Only the costs of these combinations are computed here, their terms are built in `extract`.
+        while let Some((c, e)) = worklist.pop_front() {
+            // Entries that got dominated in the meantime are covered by the entries dominating them.
+            if !pe.frontier[&c].contains(&e) { continue; }
+
+            for x in eg.usages(c) {
+                let i = eg.lookup(&x).unwrap();
+                let x = eg.class_nf(&x);
+                let children = x.applied_id_occurences();
+                let Some(frontiers) = children.iter().map(|a| pe.frontier.get(&a.id)).collect::<Option<Vec<_>>>() else { continue };
+
+                // The entry `e` is picked by one occurence of `c`, while the other occurences pick any of their entries.
+                let mut candidates = Vec::new();
+                for q in (0..children.len()).filter(|q| children[*q].id == c) {
+                    let choices: Vec<Vec<usize>> = frontiers.iter().enumerate()
+                        .map(|(k, f)| if k == q { vec![e] } else { f.to_vec() })
+                        .collect();
+                    for picks in combinations(&choices) {
+                        let costs: Vec<CF::Cost> = children.iter().zip(&picks).map(|(a, p)| pe.entries[&a.id][*p].cost.clone()).collect();
+                        let cost = cost_fn.cost(&x, occurence_costs(&x, &costs));
+                        candidates.push(Entry { enode: x.clone(), picks, cost });
+                    }
+                }
+
+                for entry in candidates {
+                    if let Some(e2) = pe.insert(i.id, entry) {
+                        worklist.push_back((i.id, e2));
+                    }
+                }
+            }
+        }
+
+        pe
+    }
+
+    /// Returns the Pareto frontier of `i`, i.e. all terms whose cost is not dominated by another term of `i`.
+    pub fn extract<N: Analysis<L>>(&self, i: AppliedId, eg: &EGraph<L, N>) -> Vec<(RecExpr<L>, CF::Cost)> {
+        let i = eg.find_applied_id(&i);
+        let Some(frontier) = self.frontier.get(&i.id) else { return Vec::new() };
+        frontier.iter()
+                .map(|e| (self.term(i.id, *e).apply_slotmap_fresh_bound(&i.m), self.entries[&i.id][*e].cost.clone()))
+                .collect()
+    }
+
+    // builds the term of the `e`-th entry of `c`, in terms of the slots of `c`.
+    fn term(&self, c: Id, e: usize) -> RecExpr<L> {
+        let entry = &self.entries[&c][e];
+        let children = entry.enode.applied_id_occurences().iter().zip(&entry.picks)
+                            .map(|(a, p)| self.term(a.id, *p).apply_slotmap_fresh_bound(&a.m))
+                            .collect();
+        RecExpr {
+            node: entry.enode.clone(),
+            children,
+        }
+    }
+
+    // adds `entry` to the frontier of `c`, and returns its index if it isn't dominated.
+    fn insert(&mut self, c: Id, entry: Entry<L, CF::Cost>) -> Option<usize> {
+        let entries = self.entries.entry(c).or_default();
+        let frontier = self.frontier.entry(c).or_default();
+        if frontier.iter().any(|e| entries[*e].cost <= entry.cost) {
+            return None;
+        }
+
+        frontier.retain(|e| !matches!(entry.cost.partial_cmp(&entries[*e].cost), Some(Ordering::Less | Ordering::Equal)));
+        frontier.push(entries.len());
+        entries.push(entry);
+        Some(entries.len() - 1)
+    }
+}
+
+// all ways to pick one element of each of the `choices`.
+fn combinations(choices: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut out = vec![Vec::new()];
+    for c in choices {
+        out = out.into_iter()
+                 .flat_map(|picks: Vec<usize>| c.iter().map(move |x| {
+                     let mut picks = picks.clone();
+                     picks.push(*x);
+                     picks
+                 }))
+                 .collect();
+    }
+    out
+}
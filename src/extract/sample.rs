@@ -0,0 +1,169 @@
+use crate::*;
+
+/// A source of randomness for a [TermSampler].
+///
+/// It is implemented for every `FnMut() -> u64`, so if you use the `rand` crate, you can simply pass `|| rng.gen()`.
+pub trait RandomSource {
+    /// Returns a uniformly distributed random number.
+    fn next_u64(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> RandomSource for F {
+    fn next_u64(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// Samples random terms of e-classes, consisting of at most `max_size` e-nodes.
+///
+/// Creating a TermSampler counts the terms of all e-classes (like [count_terms]), which then allows you to draw many samples efficiently.
+/// The binders of each sampled term get fresh names.
+pub struct TermSampler<L: Language> {
+    // the e-nodes of each e-class, in terms of the slots of their e-class.
+    nodes: HashMap<Id, Vec<L>>,
+
+    // `exact[c][s]` is the number of terms of the e-class `c` with exactly `s` e-nodes.
+    exact: HashMap<Id, Vec<u64>>,
+
+    max_size: usize,
+}
+
+impl<L: Language> TermSampler<L> {
+    pub fn new<N: Analysis<L>>(eg: &EGraph<L, N>, max_size: usize) -> Self {
+        let nodes = class_nodes(eg);
+        let exact = exact_counts(&nodes, max_size);
+        TermSampler { nodes, exact, max_size }
+    }
+
+    /// Samples a term of `i` uniformly among all of its terms with at most `max_size` e-nodes.
+    ///
+    /// These are the terms counted by [count_terms], i.e. there is one term per combination of e-nodes.
+    /// Returns `None` if `i` has no such term, or if it was created after the TermSampler.
+    /// If the number of terms exceeds `u64::MAX`, the distribution is only approximately uniform.
+    pub fn sample<N: Analysis<L>>(&self, i: AppliedId, eg: &EGraph<L, N>, rng: &mut impl RandomSource) -> Option<RecExpr<L>> {
+        let i = eg.find_applied_id(&i);
+        let counts = self.exact.get(&i.id)?;
+        let s = pick(rng, counts)?;
+        self.sample_exact(&i, s, eg, rng)
+    }
+
+    // samples a term of `i` with exactly `s` e-nodes.
+    fn sample_exact<N: Analysis<L>>(&self, i: &AppliedId, s: usize, eg: &EGraph<L, N>, rng: &mut impl RandomSource) -> Option<RecExpr<L>> {
+        let i = eg.find_applied_id(i);
+        let ns = self.nodes.get(&i.id)?;
+        let weights: Vec<u64> = ns.iter().map(|n| count_combinations(n, &self.exact, s - 1)).collect();
+        let n = &ns[pick(rng, &weights).unwrap()];
+        let child_ids: Vec<Id> = n.applied_id_occurences().iter().map(|x| x.id).collect();
+
+        // `suffix[j][t]` is the number of ways to fill the children `j..` using exactly `t` e-nodes.
+        let budget = s - 1;
+        let mut suffix = vec![vec![0u64; budget + 1]; child_ids.len() + 1];
+        suffix[child_ids.len()][0] = 1;
+        for j in (0..child_ids.len()).rev() {
+            let child = &self.exact[&child_ids[j]];
+            for t in 0..=budget {
+                for t1 in 1..=t {
+                    suffix[j][t] = child[t1].saturating_mul(suffix[j+1][t - t1]).saturating_add(suffix[j][t]);
+                }
+            }
+        }
+
+        let n = eg.enode_applied(n, &i);
+        let mut remaining = budget;
+        let mut children = Vec::new();
+        for (j, c) in n.applied_id_occurences().iter().enumerate() {
+            let child = &self.exact[&child_ids[j]];
+            let weights: Vec<u64> = (0..=remaining).map(|t1| {
+                if t1 == 0 { 0 } else { child[t1].saturating_mul(suffix[j+1][remaining - t1]) }
+            }).collect();
+            let t1 = pick(rng, &weights).unwrap();
+            children.push(self.sample_exact(c, t1, eg, rng)?);
+            remaining -= t1;
+        }
+
+        Some(RecExpr { node: n, children })
+    }
+
+    /// Samples a term of `i` with at most `max_size` e-nodes, by choosing the e-nodes randomly according to `weight`.
+    ///
+    /// Starting from `i`, each e-class chooses one of its e-nodes with a probability proportional to its weight.
+    /// E-nodes that don't fit into the remaining size, or that have a non-positive weight, are never chosen.
+    /// Returns `None` if this leaves some e-class without any e-node to choose, or if `i` was created after the TermSampler.
+    pub fn sample_weighted<N: Analysis<L>>(&self, i: AppliedId, eg: &EGraph<L, N>, weight: impl Fn(&L) -> f64, rng: &mut impl RandomSource) -> Option<RecExpr<L>> {
+        self.sample_weighted_impl(&i, self.max_size, eg, &weight, rng).map(|(re, _)| re)
+    }
+
+    // returns the term, together with its size.
+    fn sample_weighted_impl<N: Analysis<L>>(&self, i: &AppliedId, budget: usize, eg: &EGraph<L, N>, weight: &impl Fn(&L) -> f64, rng: &mut impl RandomSource) -> Option<(RecExpr<L>, usize)> {
+        let i = eg.find_applied_id(i);
+
+        // the size needed by each e-node, if its children are as small as possible.
+        let needed = |n: &L| {
+            n.applied_id_occurences().iter()
+             .map(|x| self.min_size(x.id))
+             .try_fold(1, |acc, s| Some(acc + s?))
+        };
+
+        let candidates: Vec<(&L, usize, f64)> = self.nodes.get(&i.id)?.iter()
+            .filter_map(|n| {
+                let s = needed(n)?;
+                let w = weight(n);
+                (s <= budget && w > 0.0).then_some((n, s, w))
+            })
+            .collect();
+        let total: f64 = candidates.iter().map(|(_, _, w)| w).sum();
+        if candidates.is_empty() { return None; }
+
+        // a uniformly distributed float in [0, total).
+        let mut r = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64 * total;
+        let mut k = candidates.len() - 1;
+        for (j, (_, _, w)) in candidates.iter().enumerate() {
+            if r < *w {
+                k = j;
+                break;
+            }
+            r -= w;
+        }
+        let (n, min, _) = candidates[k];
+
+        // Every child may use the size that is left over by the other children.
+        let mut spare = budget - min;
+        let mut size = 1;
+        let n = eg.enode_applied(n, &i);
+        let mut children = Vec::new();
+        for c in n.applied_id_occurences() {
+            let child_min = self.min_size(c.id).unwrap();
+            let (re, s) = self.sample_weighted_impl(&c, child_min + spare, eg, weight, rng)?;
+            spare -= s - child_min;
+            size += s;
+            children.push(re);
+        }
+
+        Some((RecExpr { node: n, children }, size))
+    }
+
+    // the size of the smallest term of `i`, if it has a term with at most `max_size` e-nodes.
+    fn min_size(&self, i: Id) -> Option<usize> {
+        self.exact.get(&i)?.iter().position(|x| *x > 0)
+    }
+}
+
+// picks an index randomly, with a probability proportional to its weight.
+// Returns `None` if all weights are zero.
+fn pick(rng: &mut impl RandomSource, weights: &[u64]) -> Option<usize> {
+    let total: u128 = weights.iter().map(|w| *w as u128).sum();
+    if total == 0 { return None; }
+
+    // We reject the random numbers above the largest multiple of `total`, so that the result is exactly uniform.
+    let zone = u128::MAX - u128::MAX % total;
+    let mut r = loop {
+        let r = ((rng.next_u64() as u128) << 64) | rng.next_u64() as u128;
+        if r < zone { break r % total; }
+    };
+
+    for (k, w) in weights.iter().enumerate() {
+        if r < *w as u128 { return Some(k); }
+        r -= *w as u128;
+    }
+    unreachable!()
+}
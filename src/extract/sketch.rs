@@ -0,0 +1,65 @@
+use crate::*;
+
+impl<L: Language, CF, C: Ord + Clone + Debug> Extractor<L, CF, C> {
+    /// Extracts the cheapest term of `i` that matches the pattern `sketch`, or `None` if there is no such term.
+    ///
+    /// The e-nodes of the sketch are matched using e-matching, and its pattern variables are filled with the cheapest terms from the extraction-table.
+    /// The sketch may not contain substitutions `b[x := t]`.
+    pub fn extract_sketch<N: Analysis<L>>(&self, i: AppliedId, sketch: &Pattern<L>, eg: &EGraph<L, N>) -> Option<RecExpr<L>> where CF: AnalysisCostFunction<L, N, Cost=C> {
+        let mut best: Option<(RecExpr<L>, C)> = None;
+        for (root, subst) in ematch_applied_id(eg, sketch, &i) {
+            let Some((re, cost, _)) = self.instantiate(sketch, &subst, eg) else { continue };
+            if best.as_ref().map(|(_, c)| cost < *c).unwrap_or(true) {
+                // `root` maps the slots of the e-class to the slots of the pattern, and `i` maps them to the slots of the caller.
+                let m = root.m.inverse().compose_partial(&i.m);
+                best = Some((re.apply_slotmap_fresh_bound(&m), cost));
+            }
+        }
+        best.map(|(re, _)| re)
+    }
+
+    // fills the pattern variables of `pattern` with the terms from the extraction-table, and computes the cost of the result.
+    // Also returns the e-class matched by `pattern`.
+    fn instantiate<N: Analysis<L>>(&self, pattern: &Pattern<L>, subst: &Subst, eg: &EGraph<L, N>) -> Option<(RecExpr<L>, C, AppliedId)> where CF: AnalysisCostFunction<L, N, Cost=C> {
+        match pattern {
+            Pattern::ENode(n, children) => {
+                let mut child_res = Vec::new();
+                let mut child_costs = Vec::new();
+                let mut child_ids = Vec::new();
+                for c in children {
+                    let (re, cost, a) = self.instantiate(c, subst, eg)?;
+                    child_res.push(re);
+                    child_costs.push(cost);
+                    child_ids.push(a);
+                }
+
+                // The sketch node is costed as the e-node it matched, but with the costs of the terms we picked for its children.
+                let mut n = n.clone();
+                for (x, a) in n.applied_id_occurences_mut().into_iter().zip(child_ids) {
+                    *x = a;
+                }
+                let cost = self.cost_fn.cost(eg, &n, occurence_costs(&n, &child_costs));
+                let a = eg.lookup(&n)?;
+
+                Some((RecExpr { node: nullify_app_ids(&n), children: child_res }, cost, a))
+            },
+            Pattern::PVar(v) | Pattern::PVarExcept(v, _) => self.extract_hole(&subst[v], eg),
+            Pattern::PVarApplied(v, args) => {
+                let m = args.iter().enumerate().map(|(k, s)| (Slot::numeric(k as u32), *s)).collect();
+                self.extract_hole(&subst[v].apply_slotmap(&m), eg)
+            },
+            Pattern::Subst(..) => panic!("Extractor::extract_sketch: sketches may not contain substitutions!"),
+        }
+    }
+
+    fn extract_hole<N: Analysis<L>>(&self, a: &AppliedId, eg: &EGraph<L, N>) -> Option<(RecExpr<L>, C, AppliedId)> {
+        let re = self.try_extract(a.clone(), eg).ok()?;
+        let cost = self.map[&eg.find_id(a.id)].1.clone();
+        Some((re, cost, a.clone()))
+    }
+}
+
+/// Extracts the cheapest term of `i` that matches the pattern `sketch`, see [Extractor::extract_sketch].
+pub fn sketch_extract<L: Language, N: Analysis<L>, CF: AnalysisCostFunction<L, N> + Default>(i: AppliedId, sketch: &Pattern<L>, eg: &EGraph<L, N>) -> Option<RecExpr<L>> {
+    Extractor::new(eg, CF::default()).extract_sketch(i, sketch, eg)
+}
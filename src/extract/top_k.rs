@@ -0,0 +1,152 @@
+use crate::*;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::marker::PhantomData;
+
+/// Like an [Extractor], but it keeps the `k` cheapest terms of every e-class.
+///
+/// Terms that only differ in the names of their bound slots count as the same term.
+///
+/// An e-node that uses the same e-class multiple times may get a different term for each occurence, see `occurence_costs` for how they are costed.
+///
+/// Like for an [Extractor], `C` is the cost type of the cost function.
+pub struct TopKExtractor<L: Language, CF, C = <CF as CostFunction<L>>::Cost> {
+    // the k cheapest terms of each e-class, sorted by cost.
+    // The terms use the slots of their e-class.
+    map: HashMap<Id, Vec<Entry<L, C>>>,
+
+    cost_fn: PhantomData<CF>,
+}
+
+struct Entry<L: Language, C> {
+    term: RecExpr<L>,
+
+    // used to detect duplicates.
+    key: AlphaRecExpr<L>,
+
+    cost: C,
+}
+
+// An e-node, given by its e-class and its index in `class_nodes`.
+type NodeKey = (Id, usize);
+
+// A term of an e-node, which picks the `idx[q]`-th entry of the `q`-th child.
+type Pick = (NodeKey, Vec<usize>);
+
+impl<L: Language, CF, C: Ord + Clone + Debug> TopKExtractor<L, CF, C> {
+    pub fn new<N: Analysis<L>>(eg: &EGraph<L, N>, cost_fn: CF, k: usize) -> Self where CF: AnalysisCostFunction<L, N, Cost=C> {
+        if CHECKS {
+            eg.check();
+        }
+
+        let nodes = class_nodes(eg);
+        let mut tk = TopK {
+            eg,
+            cost_fn: &cost_fn,
+            k,
+            nodes: &nodes,
+            map: HashMap::default(),
+            seen: HashSet::default(),
+            waiting: HashMap::default(),
+            queue: BinaryHeap::new(),
+        };
+        if k == 0 {
+            return Self { map: tk.map, cost_fn: PhantomData };
+        }
+
+        let mut ids: Vec<Id> = nodes.keys().copied().collect();
+        ids.sort();
+        for &c in &ids {
+            for (j, n) in nodes[&c].iter().enumerate() {
+                tk.schedule((c, j), vec![0; n.applied_id_occurences().len()]);
+            }
+        }
+
+        // Like in `Extractor::new`, the candidates are handled in the order of their cost,
+        // so every e-class receives its terms sorted by cost.
+        while let Some(Reverse((cost, ((c, j), idx)))) = tk.queue.pop() {
+            let entries = tk.map.entry(c).or_default();
+            if entries.len() == k { continue; }
+
+            let n = &nodes[&c][j];
+            let term = RecExpr {
+                node: n.clone(),
+                children: n.applied_id_occurences().iter().zip(&idx)
+                           .map(|(a, r)| tk.map[&a.id][*r].term.apply_slotmap_fresh_bound(&a.m))
+                           .collect(),
+            };
+            let key = AlphaRecExpr::new(&term);
+            let entries = tk.map.get_mut(&c).unwrap();
+            if !entries.iter().any(|e| e.key == key) {
+                entries.push(Entry { term, key, cost });
+
+                // Candidates that were waiting for this entry might be ready now.
+                let r = entries.len() - 1;
+                for (m, idx) in tk.waiting.remove(&(c, r)).unwrap_or_default() {
+                    tk.schedule_ready(m, idx);
+                }
+            }
+
+            // The next candidates of the e-node pick the next entry for one of the children.
+            for q in 0..idx.len() {
+                let mut next = idx.clone();
+                next[q] += 1;
+                tk.schedule((c, j), next);
+            }
+        }
+
+        tk.map.retain(|_, v| !v.is_empty());
+        Self { map: tk.map, cost_fn: PhantomData }
+    }
+
+    /// Returns up to `k` cheapest terms of `i`, sorted by their cost.
+    pub fn extract<N: Analysis<L>>(&self, i: AppliedId, eg: &EGraph<L, N>) -> Vec<(RecExpr<L>, C)> {
+        let i = eg.find_applied_id(&i);
+        let Some(entries) = self.map.get(&i.id) else { return Vec::new() };
+        entries.iter()
+               .map(|e| (e.term.apply_slotmap_fresh_bound(&i.m), e.cost.clone()))
+               .collect()
+    }
+}
+
+struct TopK<'a, L: Language, N: Analysis<L>, CF: AnalysisCostFunction<L, N>> {
+    eg: &'a EGraph<L, N>,
+    cost_fn: &'a CF,
+    k: usize,
+    nodes: &'a HashMap<Id, Vec<L>>,
+
+    map: HashMap<Id, Vec<Entry<L, CF::Cost>>>,
+
+    // the candidates that were already scheduled.
+    seen: HashSet<Pick>,
+
+    // the candidates waiting for the `r`-th entry of an e-class.
+    waiting: HashMap<(Id, usize), Vec<Pick>>,
+
+    queue: BinaryHeap<Reverse<(CF::Cost, Pick)>>,
+}
+
+impl<'a, L: Language, N: Analysis<L>, CF: AnalysisCostFunction<L, N>> TopK<'a, L, N, CF> {
+    fn schedule(&mut self, m: NodeKey, idx: Vec<usize>) {
+        if idx.iter().any(|r| *r >= self.k) { return; }
+        if !self.seen.insert((m, idx.clone())) { return; }
+        self.schedule_ready(m, idx);
+    }
+
+    // puts the candidate into the queue, or lets it wait for the first entry that is still missing.
+    fn schedule_ready(&mut self, (c, j): NodeKey, idx: Vec<usize>) {
+        let n = &self.nodes[&c][j];
+        let children = n.applied_id_occurences();
+        for (a, r) in children.iter().zip(&idx) {
+            if self.map.get(&a.id).map(|v| v.len()).unwrap_or(0) <= *r {
+                self.waiting.entry((a.id, *r)).or_default().push(((c, j), idx));
+                return;
+            }
+        }
+
+        let costs: Vec<CF::Cost> = children.iter().zip(&idx).map(|(a, r)| self.map[&a.id][*r].cost.clone()).collect();
+        let cost = self.cost_fn.cost(self.eg, n, occurence_costs(n, &costs));
+        self.queue.push(Reverse((cost, ((c, j), idx))));
+    }
+}
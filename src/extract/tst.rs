@@ -0,0 +1,114 @@
+use crate::*;
+
+// A small language for the tests that need access to the internals of the e-graph.
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+enum Tst {
+    F(AppliedId),
+    G(AppliedId, AppliedId),
+    Leaf(u32),
+}
+
+impl Language for Tst {
+    fn all_slot_occurences_mut(&mut self) -> Vec<&mut Slot> {
+        self.applied_id_occurences_mut().into_iter().flat_map(|x| x.slots_mut()).collect()
+    }
+
+    fn public_slot_occurences_mut(&mut self) -> Vec<&mut Slot> {
+        self.all_slot_occurences_mut()
+    }
+
+    fn applied_id_occurences_mut(&mut self) -> Vec<&mut AppliedId> {
+        match self {
+            Tst::F(x) => vec![x],
+            Tst::G(x, y) => vec![x, y],
+            Tst::Leaf(_) => vec![],
+        }
+    }
+
+    fn to_op(&self) -> (String, Vec<Child>) {
+        match self.clone() {
+            Tst::F(x) => (String::from("f"), vec![Child::AppliedId(x)]),
+            Tst::G(x, y) => (String::from("g"), vec![Child::AppliedId(x), Child::AppliedId(y)]),
+            Tst::Leaf(u) => (format!("{}", u), vec![]),
+        }
+    }
+
+    fn from_op(op: &str, children: Vec<Child>) -> Option<Self> {
+        match (op, &*children) {
+            ("f", [Child::AppliedId(x)]) => Some(Tst::F(x.clone())),
+            ("g", [Child::AppliedId(x), Child::AppliedId(y)]) => Some(Tst::G(x.clone(), y.clone())),
+            (op, []) => op.parse().ok().map(Tst::Leaf),
+            _ => None,
+        }
+    }
+}
+
+fn add(eg: &mut EGraph<Tst>, s: &str) -> AppliedId {
+    eg.add_expr(RecExpr::parse(s).unwrap())
+}
+
+#[test]
+fn dag_extract_unextractable_root() {
+    let mut eg = EGraph::<Tst>::new();
+    let x = add(&mut eg, "0");
+    let y = add(&mut eg, "(f 0)");
+    let z = add(&mut eg, "(g 1 (f 0))");
+    eg.clear_class(x.id);
+
+    assert_eq!(dag_extract(y.clone(), &eg, &AstSize).unwrap_err(), ExtractionError::Unextractable(y.id));
+    assert_eq!(dag_extract_exact(z.clone(), &eg, &AstSize).unwrap_err(), ExtractionError::Unextractable(z.id));
+
+    let w = add(&mut eg, "(g 1 1)");
+    assert_eq!(dag_extract(w, &eg, &AstSize).unwrap().cost, 2);
+}
+
+#[test]
+fn extractor_unextractable_class() {
+    let mut eg = EGraph::<Tst>::new();
+    let x = add(&mut eg, "0");
+    let y = add(&mut eg, "(f 0)");
+    let z = add(&mut eg, "(g 1 (f 0))");
+    let w = add(&mut eg, "1");
+    eg.clear_class(x.id);
+
+    let mut ids = [x.id, y.id, z.id];
+    ids.sort();
+
+    let ex = Extractor::<_, AstSize>::new(&eg, AstSize);
+    assert_eq!(ex.unextractable(), &ids[..]);
+    assert_eq!(ex.try_extract(y.clone(), &eg), Err(ExtractionError::Unextractable(y.id)));
+    assert_eq!(ex.try_extract(z.clone(), &eg), Err(ExtractionError::Unextractable(z.id)));
+    assert_eq!(try_extract::<_, _, AstSize>(z.clone(), &eg), Err(ExtractionError::Unextractable(z.id)));
+    assert_eq!(ex.extract(w, &eg).to_string(), "1");
+}
+
+#[test]
+fn refresh_unextractable_class() {
+    let mut eg = EGraph::<Tst>::new();
+    let x = add(&mut eg, "0");
+    let z = add(&mut eg, "(g 1 (f 0))");
+    eg.clear_class(x.id);
+
+    let mut ex = Extractor::<_, AstSize>::new(&eg, AstSize);
+    assert_eq!(ex.unextractable().len(), 3);
+
+    // `x` gets a term again, and so do the e-classes using it.
+    let x2 = add(&mut eg, "2");
+    eg.union(&x, &x2);
+    ex.refresh(&eg);
+    assert!(ex.unextractable().is_empty());
+    assert_eq!(ex.extract(z, &eg).to_string(), "(g 1 (f 2))");
+}
+
+#[test]
+fn extract_with_lets_unextractable() {
+    let mut eg = EGraph::<Tst>::new();
+    let x = add(&mut eg, "0");
+    let z = add(&mut eg, "(g (f 0) (f 0))");
+    eg.clear_class(x.id);
+
+    let mk_let = |_, _, b| b;
+    let mk_var = |_| RecExpr { node: Tst::Leaf(0), children: vec![] };
+    let ex = Extractor::<_, AstSize>::new(&eg, AstSize);
+    assert_eq!(ex.extract_with_lets(z.clone(), &eg, mk_let, mk_var), Err(ExtractionError::Unextractable(z.id)));
+}
@@ -55,5 +55,8 @@ pub use extract::*;
 mod rewrite;
 pub use rewrite::*;
 
+mod runner;
+pub use runner::*;
+
 mod group;
 use group::*;
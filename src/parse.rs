@@ -7,7 +7,9 @@ pub enum ParseError {
     RemainingRest(Vec<Token>),
     FromOpFailed(String, Vec<Child>),
     ExpectedColonEquals(Vec<Token>),
-    ExpectedRBracket(Vec<Token>)
+    ExpectedRBracket(Vec<Token>),
+    ExpectedSlotSet(String),
+    DuplicateSlot(Slot),
 }
 
 #[derive(Debug, Clone)]
@@ -20,11 +22,14 @@ enum Token {
     RParen, // )
     LBracket, // [
     RBracket, // ]
+    LBrace, // {
+    RBrace, // }
+    Backslash, // \
 }
 
 fn ident_char(c: char) -> bool {
     if c.is_whitespace() { return false; }
-    if "()[]$:=?".contains(c) { return false; }
+    if "()[]{}\\$:=?".contains(c) { return false; }
     true
 }
 
@@ -60,6 +65,15 @@ fn tokenize(mut s: &str) -> Result<Vec<Token>, ParseError> {
         } else if s.starts_with(']') {
             tokens.push(Token::RBracket);
             s = &s[1..];
+        } else if s.starts_with('{') {
+            tokens.push(Token::LBrace);
+            s = &s[1..];
+        } else if s.starts_with('}') {
+            tokens.push(Token::RBrace);
+            s = &s[1..];
+        } else if s.starts_with('\\') {
+            tokens.push(Token::Backslash);
+            s = &s[1..];
         } else if s.starts_with(":=") {
             tokens.push(Token::ColonEquals);
             s = &s[2..];
@@ -125,8 +139,18 @@ fn parse_pattern<L: Language>(tok: &[Token]) -> Result<(Pattern<L>, &[Token]), P
 
 fn parse_pattern_nosubst<L: Language>(mut tok: &[Token]) -> Result<(Pattern<L>, &[Token]), ParseError> {
     if let Token::PVar(p) = &tok[0] {
+        tok = &tok[1..];
+        if let Some(Token::Backslash) = tok.first() {
+            let (slots, tok) = parse_slot_set(&tok[1..])?;
+            return Ok((Pattern::PVarExcept(p.to_string(), slots), tok));
+        }
+        // `?f[$x $y]` is a higher-order pattern variable, whereas `?b[(var $x) := ?t]` is a substitution.
+        if let (Some(Token::LBracket), Some(Token::Slot(_) | Token::RBracket)) = (tok.first(), tok.get(1)) {
+            let (args, tok) = parse_slot_args(&tok[1..])?;
+            return Ok((Pattern::PVarApplied(p.to_string(), args), tok));
+        }
         let pat = Pattern::PVar(p.to_string());
-        return Ok((pat, &tok[1..]));
+        return Ok((pat, tok));
     }
 
     if let Token::LParen = tok[0] {
@@ -168,6 +192,42 @@ fn parse_pattern_nosubst<L: Language>(mut tok: &[Token]) -> Result<(Pattern<L>,
     }
 }
 
+// parses `{$a $b}`.
+fn parse_slot_set(mut tok: &[Token]) -> Result<(Vec<Slot>, &[Token]), ParseError> {
+    let Some(Token::LBrace) = tok.first() else { return Err(ParseError::ExpectedSlotSet(format!("{tok:?}"))) };
+    tok = &tok[1..];
+
+    let mut slots = Vec::new();
+    loop {
+        match tok.first() {
+            Some(Token::Slot(s)) => slots.push(*s),
+            Some(Token::RBrace) => break,
+            _ => return Err(ParseError::ExpectedSlotSet(format!("{tok:?}"))),
+        }
+        tok = &tok[1..];
+    }
+    slots.sort();
+    slots.dedup();
+
+    Ok((slots, &tok[1..]))
+}
+
+// parses `$a $b]`, i.e. the arguments of a higher-order pattern variable after the `[`.
+fn parse_slot_args(mut tok: &[Token]) -> Result<(Vec<Slot>, &[Token]), ParseError> {
+    let mut args = Vec::new();
+    loop {
+        match tok.first() {
+            Some(Token::Slot(s)) if args.contains(s) => return Err(ParseError::DuplicateSlot(*s)),
+            Some(Token::Slot(s)) => args.push(*s),
+            Some(Token::RBracket) => break,
+            _ => return Err(ParseError::ExpectedRBracket(to_vec(tok))),
+        }
+        tok = &tok[1..];
+    }
+
+    Ok((args, &tok[1..]))
+}
+
 enum ChildImpl<L: Language> {
     Pattern(Pattern<L>),
     Slot(Slot),
@@ -210,6 +270,14 @@ impl<L: Language> std::fmt::Display for Pattern<L> {
                 write!(f, ")")
             }
             Pattern::PVar(p) => write!(f, "?{p}"),
+            Pattern::PVarExcept(p, slots) => {
+                let slots: Vec<String> = slots.iter().map(|s| s.to_string()).collect();
+                write!(f, "?{p} \\ {{{}}}", slots.join(" "))
+            },
+            Pattern::PVarApplied(p, args) => {
+                let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+                write!(f, "?{p}[{}]", args.join(" "))
+            },
             Pattern::Subst(b, x, t) => write!(f, "{b}[{x} := {t}]"),
         }
     }
@@ -2,77 +2,20 @@ use crate::*;
 
 pub type Subst = HashMap<String, AppliedId>;
 
-#[derive(Default, Clone)]
-struct State {
-    // uses egraph slots.
-    partial_subst: Subst,
-
-    // maps from the egraph slots to the pattern slots.
-    partial_slotmap: SlotMap,
-}
-
 pub fn ematch_all<L: Language, N: Analysis<L>>(eg: &EGraph<L, N>, pattern: &Pattern<L>) -> Vec<Subst> {
-    let mut out = Vec::new();
-    for i in eg.ids() {
-        let i = eg.mk_sem_identity_applied_id(i);
-        out.extend(
-            ematch_impl(pattern, State::default(), i, eg)
-                .into_iter()
-                .map(final_subst)
-        );
-    }
-    out
+    CompiledPattern::new(pattern).search(eg)
 }
 
-// `i` uses egraph slots instead of pattern slots.
-fn ematch_impl<L: Language, N: Analysis<L>>(pattern: &Pattern<L>, st: State, i: AppliedId, eg: &EGraph<L, N>) -> Vec<State> {
-    match &pattern {
-        Pattern::PVar(v) => {
-            let mut st = st;
-            if let Some(j) = st.partial_subst.get(v) {
-                if !eg.eq(&i, j) { return Vec::new(); }
-            } else {
-                st.partial_subst.insert(v.clone(), i);
-            }
-            vec![st]
-        },
-        Pattern::ENode(n, children) => {
-            let mut out = Vec::new();
-            for nn in eg.enodes_applied(&i) {
-                'nodeloop: for n2 in eg.get_group_compatible_weak_variants(&nn) {
-                    if CHECKS {
-                        assert_eq!(&nullify_app_ids(n), n);
-                    }
-
-                    let clear_n2 = nullify_app_ids(&n2);
-                    // We can use weak_shape here, as the inputs are nullified
-                    // i.e. they only have id0() without slot args, so there are no permutations possible.
-                    let (n_sh, _) = n.weak_shape();
-                    let (clear_n2_sh, _) = clear_n2.weak_shape();
-                    if n_sh != clear_n2_sh { continue 'nodeloop; }
-
-                    let mut st = st.clone();
-
-                    for (x, y) in clear_n2.all_slot_occurences().into_iter().zip(n.all_slot_occurences().into_iter()) {
-                        if !try_insert_compatible_slotmap_bij(x, y, &mut st.partial_slotmap) { continue 'nodeloop; }
-                    }
-
-                    let mut acc = vec![st];
-                    for (sub_id, sub_pat) in n2.applied_id_occurences().into_iter().zip(children.iter()) {
-                        let mut next = Vec::new();
-                        for a in acc {
-                            next.extend(ematch_impl(sub_pat, a, sub_id.clone(), eg));
-                        }
-                        acc = next;
-                    }
+/// Like [ematch_all], but also returns the e-class in which each match is rooted.
+///
+/// The root uses the slot names of the pattern, so `pattern_subst(eg, pattern, &subst)` would be equal to it.
+pub fn ematch_all_with_roots<L: Language, N: Analysis<L>>(eg: &EGraph<L, N>, pattern: &Pattern<L>) -> Vec<(AppliedId, Subst)> {
+    CompiledPattern::new(pattern).search_with_roots(eg)
+}
 
-                    out.extend(acc);
-                }
-            }
-            out
-        },
-        Pattern::Subst(..) => panic!(),
-    }
+/// Matches `pattern` only against the e-class `i`.
+pub fn ematch_applied_id<L: Language, N: Analysis<L>>(eg: &EGraph<L, N>, pattern: &Pattern<L>, i: &AppliedId) -> Vec<(AppliedId, Subst)> {
+    CompiledPattern::new(pattern).search_applied_id(i, eg)
 }
 
 pub(crate) fn nullify_app_ids<L: Language>(l: &L) -> L {
@@ -82,33 +25,3 @@ pub(crate) fn nullify_app_ids<L: Language>(l: &L) -> L {
     }
     l
 }
-
-fn try_insert_compatible_slotmap_bij(k: Slot, v: Slot, map: &mut SlotMap) -> bool {
-    if let Some(v_old) = map.get(k) {
-        if v_old != v { return false; }
-    }
-    map.insert(k, v);
-    map.is_bijection()
-}
-
-fn final_subst(s: State) -> Subst {
-    let State {
-        partial_subst: mut subst,
-        partial_slotmap: mut slotmap
-    } = s;
-
-    // Previously, the subst uses `egraph`-based slot names.
-    // Afterwards, the subst uses `pattern`-based slot names.
-    for (_, v) in subst.iter_mut() {
-        // All slots that are not covered by the pattern, need a fresh new name.
-        for s in v.slots() {
-            if !slotmap.contains_key(s) {
-                slotmap.insert(s, Slot::fresh());
-            }
-        }
-
-        *v = v.apply_slotmap(&slotmap);
-    }
-
-    subst
-}
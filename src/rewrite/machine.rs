@@ -0,0 +1,366 @@
+use crate::*;
+
+// An instruction of the e-matching machine.
+//
+// The machine has two kinds of registers:
+// - e-class registers, containing AppliedIds which use egraph slots.
+// - slot registers, one for each slot of the pattern. They contain the egraph slot it has been bound to.
+#[derive(Clone, Debug)]
+enum Instr<L: Language> {
+    // Matches an e-node against the e-class in register `i`.
+    // `shape` is the operator of the pattern e-node,
+    // `slots` are the slot registers corresponding to its slot occurences.
+    // The children of the matched e-node are written to the registers `out..out+arity`.
+    Bind { i: usize, shape: L, slots: Vec<usize>, out: usize },
+
+    // Checks that the e-classes in registers `a` and `b` are equal.
+    // Used when a pattern variable occurs multiple times.
+    Compare { a: usize, b: usize },
+
+    // Checks that the e-class in register `i` doesn't mention the egraph slots in the slot registers `slots`.
+    // Used for pattern variables like `?x \ {$a $b}`.
+    NotFree { i: usize, slots: Vec<usize> },
+
+    // Checks that the e-class in register `i` only mentions the egraph slots in the slot registers `slots`.
+    // Used for higher-order pattern variables like `?f[$x $y]`.
+    Within { i: usize, slots: Vec<usize> },
+
+    // Checks that the e-class in register `b` is equal to the e-class in register `a`,
+    // after renaming the egraph slots in `a_slots` to the corresponding ones in `b_slots`.
+    // Used when a higher-order pattern variable occurs multiple times.
+    CompareRenamed { a: usize, a_slots: Vec<usize>, b: usize, b_slots: Vec<usize> },
+}
+
+// A pattern variable, together with the register that it is bound to.
+#[derive(Clone, Debug)]
+struct Var {
+    name: String,
+    reg: usize,
+
+    // For higher-order pattern variables, the slot registers of their arguments.
+    args: Option<Vec<usize>>,
+}
+
+/// A [Pattern] compiled into a program for an e-matching machine.
+///
+/// Compiling a pattern once, and then searching it repeatedly is faster than calling [ematch_all] each time.
+#[derive(Clone, Debug)]
+pub struct CompiledPattern<L: Language> {
+    instrs: Vec<Instr<L>>,
+
+    vars: Vec<Var>,
+
+    // The pattern slot corresponding to each slot register.
+    slots: Vec<Slot>,
+
+    // Whether some previous instruction binds the slot register.
+    bound: Vec<bool>,
+
+    n_regs: usize,
+
+    // Matches can only change if some e-class at most `depth` levels below the root changes.
+    depth: usize,
+
+    // Checks on slot registers that haven't all been bound yet, when the pattern variable was compiled.
+    // They are run at the end.
+    deferred: Vec<Instr<L>>,
+}
+
+struct Machine {
+    regs: Vec<AppliedId>,
+    slots: Vec<Option<Slot>>,
+}
+
+impl<L: Language> CompiledPattern<L> {
+    pub fn new(pattern: &Pattern<L>) -> Self {
+        let mut c = CompiledPattern {
+            instrs: Vec::new(),
+            vars: Vec::new(),
+            slots: Vec::new(),
+            bound: Vec::new(),
+            n_regs: 1,
+            depth: depth(pattern),
+            deferred: Vec::new(),
+        };
+        c.compile(pattern, 0);
+
+        // Slot registers that are still unbound in the end, correspond to slots that no match can mention.
+        let deferred = std::mem::take(&mut c.deferred);
+        c.instrs.extend(deferred);
+        c
+    }
+
+    fn compile(&mut self, pattern: &Pattern<L>, i: usize) {
+        match pattern {
+            Pattern::PVar(v) => {
+                if let Some(a) = self.vars.iter().find(|x| &x.name == v) {
+                    assert!(a.args.is_none(), "?{v} is used both with and without arguments!");
+                    self.instrs.push(Instr::Compare { a: a.reg, b: i });
+                } else {
+                    self.vars.push(Var { name: v.clone(), reg: i, args: None });
+                }
+            },
+            Pattern::PVarExcept(v, excluded) => {
+                self.compile(&Pattern::PVar(v.clone()), i);
+
+                let slots = excluded.iter().map(|s| self.slot_reg(*s)).collect();
+                self.check_slots(Instr::NotFree { i, slots });
+            },
+            Pattern::PVarApplied(v, args) => {
+                let args: Vec<usize> = args.iter().map(|s| self.slot_reg(*s)).collect();
+                self.check_slots(Instr::Within { i, slots: args.clone() });
+
+                if let Some(a) = self.vars.iter().find(|x| &x.name == v) {
+                    let Some(a_slots) = &a.args else { panic!("?{v} is used both with and without arguments!") };
+                    assert_eq!(a_slots.len(), args.len(), "?{v} is used with different numbers of arguments!");
+
+                    // The slots of both occurences might not be bound until the end.
+                    self.deferred.push(Instr::CompareRenamed { a: a.reg, a_slots: a_slots.clone(), b: i, b_slots: args });
+                } else {
+                    self.vars.push(Var { name: v.clone(), reg: i, args: Some(args) });
+                }
+            },
+            Pattern::ENode(n, children) => {
+                let slots: Vec<usize> = nullify_app_ids(n).all_slot_occurences().into_iter().map(|s| self.slot_reg(s)).collect();
+                for r in &slots {
+                    self.bound[*r] = true;
+                }
+                let shape = operator(n);
+
+                let out = self.n_regs;
+                self.n_regs += children.len();
+                self.instrs.push(Instr::Bind { i, shape, slots, out });
+
+                for (j, child) in children.iter().enumerate() {
+                    self.compile(child, out + j);
+                }
+            },
+            Pattern::Subst(..) => panic!(),
+        }
+    }
+
+    fn slot_reg(&mut self, s: Slot) -> usize {
+        if let Some(r) = self.slots.iter().position(|x| *x == s) {
+            return r;
+        }
+        self.slots.push(s);
+        self.bound.push(false);
+        self.slots.len() - 1
+    }
+
+    // We check as early as possible, i.e. right away if all its slot registers are already bound.
+    fn check_slots(&mut self, instr: Instr<L>) {
+        let (Instr::NotFree { slots, .. } | Instr::Within { slots, .. }) = &instr else { panic!() };
+        if slots.iter().all(|r| self.bound[*r]) {
+            self.instrs.push(instr);
+        } else {
+            self.deferred.push(instr);
+        }
+    }
+
+    /// Finds all matches of this pattern in the e-graph.
+    pub fn search<N: Analysis<L>>(&self, eg: &EGraph<L, N>) -> Vec<Subst> {
+        self.search_with_roots(eg).into_iter().map(|(_, subst)| subst).collect()
+    }
+
+    /// Like [CompiledPattern::search], but also returns the e-class in which each match is rooted.
+    ///
+    /// The root uses the slot names of the pattern, just like the [Subst].
+    pub fn search_with_roots<N: Analysis<L>>(&self, eg: &EGraph<L, N>) -> Vec<(AppliedId, Subst)> {
+        // Only the e-classes containing the root operator of the pattern can match.
+        let ids = match self.instrs.first() {
+            Some(Instr::Bind { shape, .. }) => eg.classes_with_op(shape),
+            _ => eg.ids(),
+        };
+        self.search_ids(ids, eg)
+    }
+
+    /// Like [CompiledPattern::search], but only finds matches rooted in the [EGraph::dirty_classes].
+    ///
+    /// All other matches have already existed before the last call to [EGraph::clear_dirty].
+    pub fn search_dirty<N: Analysis<L>>(&self, eg: &EGraph<L, N>) -> Vec<Subst> {
+        let dirty = eg.dirty_classes(self.depth);
+        let mut ids: Vec<Id> = match self.instrs.first() {
+            Some(Instr::Bind { shape, .. }) => eg.classes_with_op(shape).into_iter().filter(|i| dirty.contains(i)).collect(),
+            _ => dirty.into_iter().collect(),
+        };
+        ids.sort();
+        self.search_ids(ids, eg).into_iter().map(|(_, subst)| subst).collect()
+    }
+
+    /// Finds all matches of this pattern rooted in the e-class `i`.
+    ///
+    /// The returned roots are equal to `i`, but use the slot names of the pattern.
+    pub fn search_applied_id<N: Analysis<L>>(&self, i: &AppliedId, eg: &EGraph<L, N>) -> Vec<(AppliedId, Subst)> {
+        let mut out = Vec::new();
+        self.run_at(i, eg, &mut out);
+        out
+    }
+
+    fn search_ids<N: Analysis<L>>(&self, ids: Vec<Id>, eg: &EGraph<L, N>) -> Vec<(AppliedId, Subst)> {
+        let mut out = Vec::new();
+        for i in ids {
+            let i = eg.mk_sem_identity_applied_id(i);
+            self.run_at(&i, eg, &mut out);
+        }
+        out
+    }
+
+    // `i` uses egraph slots instead of pattern slots.
+    fn run_at<N: Analysis<L>>(&self, i: &AppliedId, eg: &EGraph<L, N>, out: &mut Vec<(AppliedId, Subst)>) {
+        let mut m = Machine {
+            regs: vec![AppliedId::null(); self.n_regs],
+            slots: vec![None; self.slots.len()],
+        };
+        m.regs[0] = i.clone();
+        self.run(0, &mut m, eg, out);
+    }
+
+    fn run<N: Analysis<L>>(&self, pc: usize, m: &mut Machine, eg: &EGraph<L, N>, out: &mut Vec<(AppliedId, Subst)>) {
+        let Some(instr) = self.instrs.get(pc) else {
+            out.push(self.final_match(m));
+            return;
+        };
+
+        match instr {
+            Instr::Compare { a, b } => {
+                if eg.eq(&m.regs[*a], &m.regs[*b]) {
+                    self.run(pc+1, m, eg, out);
+                }
+            },
+            Instr::NotFree { i, slots } => {
+                let free = m.regs[*i].slots();
+                if slots.iter().all(|r| !m.slots[*r].is_some_and(|x| free.contains(&x))) {
+                    self.run(pc+1, m, eg, out);
+                }
+            },
+            Instr::Within { i, slots } => {
+                let allowed: HashSet<Slot> = slots.iter().filter_map(|r| m.slots[*r]).collect();
+                if m.regs[*i].slots().is_subset(&allowed) {
+                    self.run(pc+1, m, eg, out);
+                }
+            },
+            Instr::CompareRenamed { a, a_slots, b, b_slots } => {
+                // maps the egraph slots of `a` to those of `b`.
+                let mut map = SlotMap::new();
+                for (ra, rb) in a_slots.iter().zip(b_slots) {
+                    if let Some(x) = m.slots[*ra] {
+                        match m.slots[*rb] {
+                            Some(y) => map.insert(x, y),
+                            // an unbound slot can't be mentioned by `b`.
+                            None if m.regs[*a].slots().contains(&x) => return,
+                            None => {},
+                        }
+                    }
+                }
+                if eg.eq(&m.regs[*a].apply_slotmap_partial(&map), &m.regs[*b]) {
+                    self.run(pc+1, m, eg, out);
+                }
+            },
+            Instr::Bind { i, shape, slots, out: o } => {
+                let i = m.regs[*i].clone();
+                if !eg.has_op(i.id, shape) { return; }
+
+                // The operator doesn't depend on slot names.
+                // Hence, we can filter by it before the e-nodes get their slots refreshed.
+                let matches_shape = |n: &L| &operator(n) == shape;
+
+                for nn in eg.enodes_applied_filtered(&i, matches_shape) {
+                    // The group-compatible variants of `nn` only differ in the slots of their AppliedIds.
+                    // Hence, we can bind the slots of the e-node itself before computing them.
+                    let mut bound = Vec::new();
+                    let ok = nullify_app_ids(&nn).all_slot_occurences().into_iter().zip(slots.iter())
+                                .all(|(x, r)| bind_slot(x, *r, &mut m.slots, &mut bound));
+
+                    if ok {
+                        // If no child e-class has symmetries, `nn` is its only variant.
+                        let trivial = nn.applied_id_occurences().iter().all(|x| eg.classes[&x.id].group.count() == 1);
+                        let variants = if trivial {
+                            [nn].into_iter().collect()
+                        } else {
+                            eg.get_group_compatible_weak_variants(&nn)
+                        };
+
+                        for n2 in variants {
+                            for (j, x) in n2.applied_id_occurences().into_iter().enumerate() {
+                                m.regs[o+j] = x;
+                            }
+                            self.run(pc+1, m, eg, out);
+                        }
+                    }
+
+                    for r in bound {
+                        m.slots[r] = None;
+                    }
+                }
+            },
+        }
+    }
+
+    fn final_match(&self, m: &Machine) -> (AppliedId, Subst) {
+        // maps from the egraph slots to the pattern slots.
+        let mut slotmap = SlotMap::new();
+        for (r, x) in m.slots.iter().enumerate() {
+            if let Some(x) = x {
+                slotmap.insert(*x, self.slots[r]);
+            }
+        }
+
+        // Previously, the registers use `egraph`-based slot names.
+        // Afterwards, the subst uses `pattern`-based slot names.
+        let mut subst = Subst::default();
+        for Var { name: v, reg: r, args } in &self.vars {
+            let app = &m.regs[*r];
+
+            // Higher-order pattern variables use the positional slots $0, $1, ... for their arguments.
+            if let Some(args) = args {
+                let positional = args.iter().enumerate()
+                                     .filter_map(|(k, r)| m.slots[*r].map(|x| (x, Slot::numeric(k as u32))))
+                                     .collect();
+                subst.insert(v.clone(), app.apply_slotmap(&positional));
+                continue;
+            }
+
+            // All slots that are not covered by the pattern, need a fresh new name.
+            for s in app.slots() {
+                if !slotmap.contains_key(s) {
+                    slotmap.insert(s, Slot::fresh());
+                }
+            }
+
+            subst.insert(v.clone(), app.apply_slotmap(&slotmap));
+        }
+
+        let root = &m.regs[0];
+        for s in root.slots() {
+            if !slotmap.contains_key(s) {
+                slotmap.insert(s, Slot::fresh());
+            }
+        }
+
+        (root.apply_slotmap(&slotmap), subst)
+    }
+}
+
+// A pattern variable has depth 0, an e-node has depth 1 + the depth of its deepest child.
+fn depth<L: Language>(pattern: &Pattern<L>) -> usize {
+    match pattern {
+        Pattern::ENode(_, children) => 1 + children.iter().map(depth).max().unwrap_or(0),
+        Pattern::PVar(_) | Pattern::PVarExcept(..) | Pattern::PVarApplied(..) => 0,
+        Pattern::Subst(..) => panic!(),
+    }
+}
+
+// Binds the slot register `r` to the egraph slot `x`, while keeping the slot registers a bijection.
+// Newly bound registers are pushed to `bound`.
+fn bind_slot(x: Slot, r: usize, slots: &mut [Option<Slot>], bound: &mut Vec<usize>) -> bool {
+    if let Some(y) = slots[r] {
+        return x == y;
+    }
+    if slots.contains(&Some(x)) {
+        return false;
+    }
+    slots[r] = Some(x);
+    bound.push(r);
+    true
+}
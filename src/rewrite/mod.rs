@@ -4,6 +4,9 @@ use std::any::Any;
 mod ematch;
 pub use ematch::*;
 
+mod machine;
+pub use machine::*;
+
 mod pattern;
 pub use pattern::*;
 
@@ -12,8 +15,56 @@ pub use subst_method::*;
 
 /// An equational rewrite rule.
 pub struct Rewrite<L: Language, N: Analysis<L> = ()> {
-    pub(crate) searcher: Box<dyn Fn(&EGraph<L, N>) -> Box<dyn Any>>,
-    pub(crate) applier: Box<dyn Fn(Box<dyn Any>, &mut EGraph<L, N>)>,
+    pub(crate) searcher: Searcher<L, N>,
+
+    // like `searcher`, but only searches for matches rooted in the dirty e-classes.
+    pub(crate) dirty_searcher: Option<Searcher<L, N>>,
+
+    pub(crate) applier: Box<dyn Fn(Box<dyn Any>, &mut EGraph<L, N>) -> Changes>,
+
+    // counts the matches found by the searcher.
+    pub(crate) counter: Counter,
+
+    pub(crate) name: String,
+}
+
+type Searcher<L, N> = Box<dyn Fn(&EGraph<L, N>) -> Box<dyn Any>>;
+type Counter = Box<dyn Fn(&dyn Any) -> usize>;
+
+/// Describes how much applying a [Rewrite] changed the e-graph.
+///
+/// If a whole pass of [apply_rewrites] returns empty [Changes], the e-graph has reached a fixpoint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// The number of unions that actually changed the e-graph.
+    pub unions: usize,
+
+    /// The number of e-nodes that have been newly added.
+    pub new_enodes: usize,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.unions == 0 && self.new_enodes == 0
+    }
+}
+
+impl std::ops::AddAssign for Changes {
+    fn add_assign(&mut self, other: Changes) {
+        self.unions += other.unions;
+        self.new_enodes += other.new_enodes;
+    }
+}
+
+impl std::ops::Sub for Changes {
+    type Output = Changes;
+
+    fn sub(self, other: Changes) -> Changes {
+        Changes {
+            unions: self.unions - other.unions,
+            new_enodes: self.new_enodes - other.new_enodes,
+        }
+    }
 }
 
 /// Use this type when you want to build your own [Rewrite].
@@ -29,12 +80,29 @@ pub struct RewriteT<L: Language, N: Analysis<L>, T: Any> {
 
 impl<L: Language + 'static, N: Analysis<L> + 'static, T: 'static> RewriteT<L, N, T> {
     /// Use this function to convert it to an actual [Rewrite].
+    ///
+    /// The resulting [Rewrite] is called "unnamed", use [Rewrite::with_name] to change that.
+    /// It reports a single match per search to a [Scheduler].
     pub fn into(self) -> Rewrite<L, N> {
+        self.into_counted(|_| 1)
+    }
+
+    /// Like [RewriteT::into], but `count` computes the number of matches contained in a search result.
+    ///
+    /// A [Scheduler] uses this number to decide whether the search result should be applied.
+    pub fn into_counted(self, count: impl Fn(&T) -> usize + 'static) -> Rewrite<L, N> {
         let searcher = self.searcher;
         let applier = self.applier;
         Rewrite {
             searcher: Box::new(move |eg| Box::new((*searcher)(eg))),
-            applier: Box::new(move |t, eg| (*applier)(any_to_t(t), eg))
+            dirty_searcher: None,
+            applier: Box::new(move |t, eg| {
+                let old = eg.changes;
+                (*applier)(any_to_t(t), eg);
+                eg.changes - old
+            }),
+            counter: Box::new(move |t| count(t.downcast_ref().unwrap())),
+            name: String::from("unnamed"),
         }
     }
 }
@@ -44,10 +112,27 @@ fn any_to_t<T: Any>(t: Box<dyn Any>) -> T {
 }
 
 /// Applies each given rewrite rule to the E-Graph once.
-pub fn apply_rewrites<L: Language, N: Analysis<L>>(eg: &mut EGraph<L, N>, rewrites: &[Rewrite<L, N>]) {
+///
+/// Returns the accumulated [Changes] of all rewrites.
+pub fn apply_rewrites<L: Language, N: Analysis<L>>(eg: &mut EGraph<L, N>, rewrites: &[Rewrite<L, N>]) -> Changes {
     let ts: Vec<Box<dyn Any>> = rewrites.iter().map(|rw| (*rw.searcher)(eg)).collect();
+    let mut changes = Changes::default();
     for (rw, t) in rewrites.iter().zip(ts.into_iter()) {
-        (*rw.applier)(t, eg);
+        changes += (*rw.applier)(t, eg);
+    }
+    changes
+}
+
+impl<L: Language, N: Analysis<L>> Rewrite<L, N> {
+    /// The name of this rewrite rule.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Renames this rewrite rule.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
     }
 }
 
@@ -61,11 +146,17 @@ impl<L: Language + 'static, N: Analysis<L> + 'static> Rewrite<L, N> {
     pub fn new_if(rule: &str, a: &str, b: &str, cond: impl Fn(&Subst) -> bool + 'static) -> Self {
         let a = Pattern::parse(a).unwrap();
         let b = Pattern::parse(b).unwrap();
+        let mut arities = HashMap::default();
+        check_pvar_args(&a, &mut arities);
+        check_pvar_args(&b, &mut arities);
+        let name = rule;
         let rule = rule.to_string();
+        let compiled = CompiledPattern::new(&a);
+        let compiled2 = compiled.clone();
         let a2 = a.clone();
-        RewriteT {
+        let mut rw = RewriteT {
             searcher: Box::new(move |eg| {
-                let x: Vec<Subst> = ematch_all(eg, &a);
+                let x: Vec<Subst> = compiled.search(eg);
                 x
             }),
             applier: Box::new(move |substs, eg| {
@@ -75,6 +166,8 @@ impl<L: Language + 'static, N: Analysis<L> + 'static> Rewrite<L, N> {
                     }
                 }
             }),
-        }.into()
+        }.into_counted(|substs| substs.len()).with_name(name);
+        rw.dirty_searcher = Some(Box::new(move |eg| Box::new(compiled2.search_dirty(eg))));
+        rw
     }
 }
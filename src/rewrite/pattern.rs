@@ -4,10 +4,16 @@ use crate::*;
 /// A Pattern to match against, or as the rhs of a rewrite rule.
 ///
 /// - It supports pattern-variables `?x` to match against anything.
+/// - It supports (on the lhs) pattern-variables `?x \ {$a $b}` that only match e-classes not mentioning the slots `$a` and `$b`.
+/// - It supports higher-order pattern-variables `?f[$a $b]` that only match e-classes mentioning no slots other than `$a` and `$b`.
+///   On the rhs, `?f[$c $d]` instantiates `?f` with `$a`, `$b` renamed to `$c`, `$d`.
+///   In the [Subst], such a variable uses the slots `$0`, `$1`, ... for its arguments.
 /// - It supports (on the rhs) substitutions `b[x := t]` to substitute natively.
 pub enum Pattern<L: Language> {
     ENode(L, Vec<Pattern<L>>),
     PVar(String), // ?x
+    PVarExcept(String, Vec<Slot>), // ?x \ {$a $b}
+    PVarApplied(String, Vec<Slot>), // ?f[$a $b]
     Subst(Box<Pattern<L>>, Box<Pattern<L>>, Box<Pattern<L>>), // Subst(b, x, t) means `b[x := t]`
 }
 
@@ -23,9 +29,13 @@ pub fn pattern_subst<L: Language, N: Analysis<L>>(eg: &mut EGraph<L, N>, pattern
             }
             eg.add_syn(n)
         },
-        Pattern::PVar(v) => {
+        Pattern::PVar(v) | Pattern::PVarExcept(v, _) => {
             subst[v].clone()
         },
+        Pattern::PVarApplied(v, args) => {
+            let m = args.iter().enumerate().map(|(k, s)| (Slot::numeric(k as u32), *s)).collect();
+            subst[v].apply_slotmap(&m)
+        },
         Pattern::Subst(b, x, t) => {
             let b = pattern_subst(eg, &*b, subst);
             let x = pattern_subst(eg, &*x, subst);
@@ -40,6 +50,30 @@ pub fn pattern_subst<L: Language, N: Analysis<L>>(eg: &mut EGraph<L, N>, pattern
     }
 }
 
+// Checks that each higher-order pattern variable is used with the same number of arguments everywhere,
+// and that first-order pattern variables are never used with arguments.
+pub(crate) fn check_pvar_args<L: Language>(pat: &Pattern<L>, arities: &mut HashMap<String, Option<usize>>) {
+    let (v, arity) = match pat {
+        Pattern::ENode(_, children) => {
+            for c in children {
+                check_pvar_args(c, arities);
+            }
+            return;
+        },
+        Pattern::Subst(b, x, t) => {
+            check_pvar_args(b, arities);
+            check_pvar_args(x, arities);
+            check_pvar_args(t, arities);
+            return;
+        },
+        Pattern::PVar(v) | Pattern::PVarExcept(v, _) => (v, None),
+        Pattern::PVarApplied(v, args) => (v, Some(args.len())),
+    };
+    if let Some(old) = arities.insert(v.clone(), arity) {
+        assert_eq!(old, arity, "?{v} is used with inconsistent arguments!");
+    }
+}
+
 // TODO maybe move into EGraph API?
 pub fn lookup_rec_expr<L: Language, N: Analysis<L>>(re: &RecExpr<L>, eg: &EGraph<L, N>) -> Option<AppliedId> {
     let mut n = re.node.clone();
@@ -0,0 +1,281 @@
+use crate::*;
+
+use std::any::Any;
+use std::time::{Duration, Instant};
+
+mod scheduler;
+pub use scheduler::*;
+
+mod report;
+pub use report::*;
+
+/// The reason why a [Runner] stopped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The last iteration didn't change the e-graph anymore.
+    Saturated,
+
+    /// The configured number of iterations has been reached.
+    IterationLimit(usize),
+
+    /// The e-graph has grown above the configured number of e-nodes.
+    NodeLimit(usize),
+
+    /// The configured time has run out.
+    TimeLimit(Duration),
+
+    /// A hook requested the [Runner] to stop.
+    Other(String),
+}
+
+type Hook<L, N> = Box<dyn FnMut(&mut EGraph<L, N>) -> Result<(), String>>;
+
+/// Statistics about a single iteration of a [Runner].
+#[derive(Clone, Debug)]
+pub struct Iteration {
+    /// The number of e-nodes after this iteration.
+    pub egraph_nodes: usize,
+
+    /// The number of e-classes after this iteration.
+    pub egraph_classes: usize,
+
+    /// How much the rewrites changed the e-graph in this iteration.
+    pub changes: Changes,
+
+    pub search_time: Duration,
+    pub apply_time: Duration,
+    pub total_time: Duration,
+
+    /// Statistics for each rewrite that has been searched in this iteration.
+    pub rewrites: Vec<RewriteStats>,
+}
+
+/// Statistics about a single [Rewrite] within an [Iteration].
+#[derive(Clone, Debug)]
+pub struct RewriteStats {
+    /// The name of the rewrite, see [Rewrite::name].
+    pub name: String,
+
+    /// The number of matches found by the searcher.
+    pub matches: usize,
+
+    /// Whether the [Scheduler] allowed these matches to be applied.
+    pub applied: bool,
+
+    /// How much applying the matches changed the e-graph.
+    pub changes: Changes,
+
+    pub search_time: Duration,
+    pub apply_time: Duration,
+}
+
+/// Runs rewrite rules on an [EGraph] until saturation, or until some limit is reached.
+///
+/// Each iteration first searches all rewrites, and then applies all of them - just like [apply_rewrites].
+pub struct Runner<L: Language, N: Analysis<L> = ()> {
+    pub egraph: EGraph<L, N>,
+
+    /// Statistics for each iteration that has been run.
+    pub iterations: Vec<Iteration>,
+
+    /// Why the [Runner] stopped. This is `None` before [Runner::run] is called.
+    pub stop_reason: Option<StopReason>,
+
+    iter_limit: usize,
+    node_limit: usize,
+    time_limit: Duration,
+
+    hooks: Vec<Hook<L, N>>,
+
+    scheduler: Box<dyn Scheduler>,
+
+    incremental: bool,
+}
+
+impl<L: Language, N: Analysis<L>> Default for Runner<L, N> {
+    fn default() -> Self {
+        Self::new(EGraph::new())
+    }
+}
+
+impl<L: Language, N: Analysis<L>> Runner<L, N> {
+    /// Creates a [Runner] operating on `egraph`.
+    ///
+    /// By default it stops after 30 iterations, 10000 e-nodes or 5 seconds.
+    pub fn new(egraph: EGraph<L, N>) -> Self {
+        Runner {
+            egraph,
+            iterations: Vec::new(),
+            stop_reason: None,
+            iter_limit: 30,
+            node_limit: 10_000,
+            time_limit: Duration::from_secs(5),
+            hooks: Vec::new(),
+            scheduler: Box::new(SimpleScheduler),
+            incremental: false,
+        }
+    }
+
+    pub fn with_iter_limit(mut self, iter_limit: usize) -> Self {
+        self.iter_limit = iter_limit;
+        self
+    }
+
+    pub fn with_node_limit(mut self, node_limit: usize) -> Self {
+        self.node_limit = node_limit;
+        self
+    }
+
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = time_limit;
+        self
+    }
+
+    /// Sets the [Scheduler] that decides which rewrites are searched and applied in each iteration.
+    ///
+    /// The default is the [SimpleScheduler].
+    pub fn with_scheduler(mut self, scheduler: impl Scheduler + 'static) -> Self {
+        self.scheduler = Box::new(scheduler);
+        self
+    }
+
+    /// Enables incremental search.
+    ///
+    /// If all matches of a rewrite have been applied in the previous iteration,
+    /// it is only searched in the e-classes that changed since, see [EGraph::dirty_classes].
+    /// Only the rewrites created by [Rewrite::new] and [Rewrite::new_if] support this, all others are always searched fully.
+    pub fn with_incremental_search(mut self) -> Self {
+        self.incremental = true;
+        self
+    }
+
+    /// Adds a hook that runs after each iteration.
+    ///
+    /// If the hook returns `Err(s)`, the [Runner] stops with [StopReason::Other].
+    pub fn with_hook(mut self, hook: impl FnMut(&mut EGraph<L, N>) -> Result<(), String> + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Runs the rewrites until a [StopReason] is encountered.
+    pub fn run(mut self, rewrites: &[Rewrite<L, N>]) -> Self {
+        let start = Instant::now();
+
+        // Whether all matches of a rewrite up to the last `clear_dirty` have been applied.
+        let mut up_to_date = vec![false; rewrites.len()];
+
+        loop {
+            if let Some(reason) = self.check_limits(start) {
+                self.stop_reason = Some(reason);
+                break;
+            }
+
+            let iteration = self.iterations.len();
+            let iter_start = Instant::now();
+
+            let mut stats = Vec::new();
+            let mut ts = Vec::new();
+            for (i, rw) in rewrites.iter().enumerate() {
+                if !self.scheduler.can_search(iteration, i) {
+                    up_to_date[i] = false;
+                    continue;
+                }
+
+                let search_start = Instant::now();
+                let searcher = match &rw.dirty_searcher {
+                    Some(s) if self.incremental && up_to_date[i] => s,
+                    _ => &rw.searcher,
+                };
+                let t = (*searcher)(&self.egraph);
+                let matches = (*rw.counter)(&*t);
+                let applied = self.scheduler.should_apply(iteration, i, matches);
+                up_to_date[i] = applied;
+                if applied {
+                    ts.push((stats.len(), rw, t));
+                }
+                stats.push(RewriteStats {
+                    name: rw.name.clone(),
+                    matches,
+                    applied,
+                    changes: Changes::default(),
+                    search_time: search_start.elapsed(),
+                    apply_time: Duration::ZERO,
+                });
+            }
+            let search_time = iter_start.elapsed();
+
+            // All changes from now on are relevant for the next iteration.
+            if self.incremental {
+                self.egraph.clear_dirty();
+            }
+
+            let apply_start = Instant::now();
+            let mut changes = Changes::default();
+            for (j, rw, t) in ts {
+                let rw_start = Instant::now();
+                let c = (*rw.applier)(t, &mut self.egraph);
+                stats[j].changes = c;
+                stats[j].apply_time = rw_start.elapsed();
+                changes += c;
+            }
+            let apply_time = apply_start.elapsed();
+
+            self.iterations.push(Iteration {
+                egraph_nodes: self.egraph.total_number_of_nodes(),
+                egraph_classes: self.egraph.ids().len(),
+                changes,
+                search_time,
+                apply_time,
+                total_time: iter_start.elapsed(),
+                rewrites: stats,
+            });
+
+            if let Some(reason) = self.run_hooks() {
+                self.stop_reason = Some(reason);
+                break;
+            }
+
+            if changes.is_empty() && self.scheduler.can_stop(iteration) {
+                self.stop_reason = Some(StopReason::Saturated);
+                break;
+            }
+        }
+
+        self
+    }
+
+    /// Summarizes the iterations that have been run so far.
+    pub fn report(&self) -> Report {
+        Report {
+            stop_reason: self.stop_reason.clone(),
+            iterations: self.iterations.clone(),
+        }
+    }
+
+    fn run_hooks(&mut self) -> Option<StopReason> {
+        for hook in self.hooks.iter_mut() {
+            if let Err(s) = hook(&mut self.egraph) {
+                return Some(StopReason::Other(s));
+            }
+        }
+        None
+    }
+
+    fn check_limits(&self, start: Instant) -> Option<StopReason> {
+        if self.iterations.len() >= self.iter_limit {
+            return Some(StopReason::IterationLimit(self.iterations.len()));
+        }
+
+        let nodes = self.egraph.total_number_of_nodes();
+        if nodes > self.node_limit {
+            return Some(StopReason::NodeLimit(nodes));
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed > self.time_limit {
+            return Some(StopReason::TimeLimit(elapsed));
+        }
+
+        None
+    }
+}
@@ -0,0 +1,138 @@
+use crate::*;
+
+use std::fmt::{self, Display, Formatter, Write};
+use std::time::Duration;
+
+/// A summary of what a [Runner] did, obtained by [Runner::report].
+///
+/// Use its [Display] implementation to print it as a table, or [Report::to_json] to export it.
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub stop_reason: Option<StopReason>,
+    pub iterations: Vec<Iteration>,
+}
+
+impl Report {
+    pub fn total_time(&self) -> Duration {
+        self.iterations.iter().map(|it| it.total_time).sum()
+    }
+
+    /// Serializes the report as a JSON object.
+    ///
+    /// All durations are given in seconds.
+    pub fn to_json(&self) -> String {
+        let mut s = String::new();
+        let stop_reason = match &self.stop_reason {
+            Some(r) => json_string(&format!("{r:?}")),
+            None => String::from("null"),
+        };
+        write!(s, "{{\"stop_reason\": {stop_reason}, ").unwrap();
+        write!(s, "\"total_time\": {}, ", self.total_time().as_secs_f64()).unwrap();
+        s.push_str("\"iterations\": [");
+        for (i, it) in self.iterations.iter().enumerate() {
+            if i > 0 { s.push_str(", "); }
+            write!(s, "{{\"egraph_nodes\": {}, ", it.egraph_nodes).unwrap();
+            write!(s, "\"egraph_classes\": {}, ", it.egraph_classes).unwrap();
+            write!(s, "\"unions\": {}, ", it.changes.unions).unwrap();
+            write!(s, "\"new_enodes\": {}, ", it.changes.new_enodes).unwrap();
+            write!(s, "\"search_time\": {}, ", it.search_time.as_secs_f64()).unwrap();
+            write!(s, "\"apply_time\": {}, ", it.apply_time.as_secs_f64()).unwrap();
+            write!(s, "\"total_time\": {}, ", it.total_time.as_secs_f64()).unwrap();
+            s.push_str("\"rewrites\": [");
+            for (j, rw) in it.rewrites.iter().enumerate() {
+                if j > 0 { s.push_str(", "); }
+                write!(s, "{{\"name\": {}, ", json_string(&rw.name)).unwrap();
+                write!(s, "\"matches\": {}, ", rw.matches).unwrap();
+                write!(s, "\"applied\": {}, ", rw.applied).unwrap();
+                write!(s, "\"unions\": {}, ", rw.changes.unions).unwrap();
+                write!(s, "\"new_enodes\": {}, ", rw.changes.new_enodes).unwrap();
+                write!(s, "\"search_time\": {}, ", rw.search_time.as_secs_f64()).unwrap();
+                write!(s, "\"apply_time\": {}}}", rw.apply_time.as_secs_f64()).unwrap();
+            }
+            s.push_str("]}");
+        }
+        s.push_str("]}");
+        s
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn millis(d: Duration) -> String {
+    format!("{:.3}", d.as_secs_f64() * 1000.0)
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let header = ["iter", "rewrite", "matches", "unions", "new e-nodes", "search (ms)", "apply (ms)", "e-nodes", "e-classes"];
+
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for (i, it) in self.iterations.iter().enumerate() {
+            for rw in &it.rewrites {
+                // The matches of banned rewrites haven't been applied at all.
+                let (unions, new_enodes) = if rw.applied {
+                    (rw.changes.unions.to_string(), rw.changes.new_enodes.to_string())
+                } else {
+                    (String::from("-"), String::from("-"))
+                };
+                rows.push(vec![
+                    i.to_string(),
+                    rw.name.clone(),
+                    rw.matches.to_string(),
+                    unions,
+                    new_enodes,
+                    millis(rw.search_time),
+                    millis(rw.apply_time),
+                    it.egraph_nodes.to_string(),
+                    it.egraph_classes.to_string(),
+                ]);
+            }
+        }
+
+        let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+        for row in &rows {
+            for (w, x) in widths.iter_mut().zip(row) {
+                *w = (*w).max(x.len());
+            }
+        }
+
+        let write_row = |f: &mut Formatter<'_>, row: &[&str]| -> fmt::Result {
+            for (j, (x, w)) in row.iter().zip(&widths).enumerate() {
+                if j > 0 { write!(f, "  ")?; }
+                // The first two columns are text, the others are numbers.
+                if j < 2 {
+                    write!(f, "{x:<w$}")?;
+                } else {
+                    write!(f, "{x:>w$}")?;
+                }
+            }
+            writeln!(f)
+        };
+
+        write_row(f, &header)?;
+        for row in &rows {
+            let row: Vec<&str> = row.iter().map(|x| x.as_str()).collect();
+            write_row(f, &row)?;
+        }
+
+        match &self.stop_reason {
+            Some(r) => writeln!(f, "stop reason: {r:?}")?,
+            None => writeln!(f, "stop reason: -")?,
+        }
+        writeln!(f, "total time: {} ms", millis(self.total_time()))
+    }
+}
@@ -0,0 +1,113 @@
+use crate::*;
+
+/// A Scheduler decides which rewrites a [Runner] searches and applies in each iteration.
+///
+/// Rewrites are identified by their index in the slice given to [Runner::run].
+pub trait Scheduler {
+    /// Whether the rewrite `rule` should be searched in `iteration`.
+    fn can_search(&mut self, iteration: usize, rule: usize) -> bool {
+        let _ = (iteration, rule);
+        true
+    }
+
+    /// Whether the `matches` found by searching the rewrite `rule` should be applied.
+    fn should_apply(&mut self, iteration: usize, rule: usize, matches: usize) -> bool {
+        let _ = (iteration, rule, matches);
+        true
+    }
+
+    /// Called when an iteration didn't change the e-graph.
+    ///
+    /// Returning `false` prevents the [Runner] from stopping with [StopReason::Saturated],
+    /// which is necessary if some rewrites have been skipped.
+    fn can_stop(&mut self, iteration: usize) -> bool {
+        let _ = iteration;
+        true
+    }
+}
+
+/// The default [Scheduler]. It searches and applies every rewrite in every iteration.
+#[derive(Default)]
+pub struct SimpleScheduler;
+
+impl Scheduler for SimpleScheduler {}
+
+/// A [Scheduler] that temporarily bans rewrites which match too often.
+///
+/// If a rewrite finds more than `match_limit` matches, its matches are dropped and it is banned for `ban_length` iterations.
+/// Each time a rewrite gets banned, both its match limit and its ban length are doubled.
+pub struct BackoffScheduler {
+    match_limit: usize,
+    ban_length: usize,
+    stats: HashMap<usize, RuleStats>,
+}
+
+#[derive(Default)]
+struct RuleStats {
+    times_banned: u32,
+    banned_until: usize,
+}
+
+impl Default for BackoffScheduler {
+    fn default() -> Self {
+        BackoffScheduler {
+            match_limit: 1_000,
+            ban_length: 5,
+            stats: HashMap::default(),
+        }
+    }
+}
+
+impl BackoffScheduler {
+    pub fn with_initial_match_limit(mut self, match_limit: usize) -> Self {
+        self.match_limit = match_limit;
+        self
+    }
+
+    pub fn with_ban_length(mut self, ban_length: usize) -> Self {
+        self.ban_length = ban_length;
+        self
+    }
+
+    /// Whether the rewrite `rule` is banned in `iteration`.
+    pub fn is_banned(&self, iteration: usize, rule: usize) -> bool {
+        self.stats.get(&rule).map(|s| iteration < s.banned_until).unwrap_or(false)
+    }
+}
+
+impl Scheduler for BackoffScheduler {
+    fn can_search(&mut self, iteration: usize, rule: usize) -> bool {
+        !self.is_banned(iteration, rule)
+    }
+
+    fn should_apply(&mut self, iteration: usize, rule: usize, matches: usize) -> bool {
+        let stats = self.stats.entry(rule).or_default();
+        if matches <= doubled(self.match_limit, stats.times_banned) {
+            return true;
+        }
+
+        let ban_length = doubled(self.ban_length, stats.times_banned);
+        stats.times_banned += 1;
+        stats.banned_until = iteration.saturating_add(1).saturating_add(ban_length);
+        false
+    }
+
+    fn can_stop(&mut self, iteration: usize) -> bool {
+        // If some rewrites are banned, we are not saturated yet.
+        // Instead of waiting for the bans to run out, we lift them right away.
+        let mut banned = false;
+        for s in self.stats.values_mut() {
+            if iteration < s.banned_until {
+                s.banned_until = iteration;
+                banned = true;
+            }
+        }
+        !banned
+    }
+}
+
+// x * 2^n, saturating.
+fn doubled(x: usize, n: u32) -> usize {
+    let factor = 1usize.checked_shl(n).unwrap_or(usize::MAX);
+    x.saturating_mul(factor)
+}
@@ -21,7 +21,7 @@ pub struct AppliedId {
 /// A "term" or "expression" from some given [Language] L.
 // The AppliedIds in `node` are ignored (any typically set to AppliedId::null()). They are replaced by the children RecExpr.
 // A non-fancy version of RecExpr that uses the slots as "names".
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct RecExpr<L: Language> {
     pub node: L,
     pub children: Vec<RecExpr<L>>,
@@ -74,3 +74,102 @@ impl AppliedId {
         AppliedId { id: Id(0), m: SlotMap::new() }
     }
 }
+
+impl<L: Language> RecExpr<L> {
+    /// Renames the slots of this term. Free slots are renamed using `free`, and each binder gets a new name by `bound`.
+    pub(crate) fn rename_slots(&self, free: &mut impl FnMut(Slot) -> Slot, bound: &mut impl FnMut(Slot) -> Slot) -> RecExpr<L> {
+        self.rename_slots_impl(&HashMap::default(), free, bound)
+    }
+
+    fn rename_slots_impl(&self, env: &HashMap<Slot, Slot>, free: &mut impl FnMut(Slot) -> Slot, bound: &mut impl FnMut(Slot) -> Slot) -> RecExpr<L> {
+        // The AppliedIds in `node` are ignored anyways, so the remaining slots are either binders or public slots like `(var $x)`.
+        let mut node = self.node.map_applied_ids(|_| AppliedId::null());
+
+        // The private slots of `node` are bound in `node` and all of its children.
+        let private = firsts(node.private_slot_occurences());
+        let mut inner_env;
+        let env = if private.is_empty() {
+            env
+        } else {
+            inner_env = env.clone();
+            for s in private {
+                inner_env.insert(s, bound(s));
+            }
+            &inner_env
+        };
+
+        for x in node.all_slot_occurences_mut() {
+            *x = match env.get(x) {
+                Some(y) => *y,
+                None => free(*x),
+            };
+        }
+
+        let children = self.children.iter().map(|c| c.rename_slots_impl(env, free, bound)).collect();
+        RecExpr { node, children }
+    }
+
+    /// The free slots of this term, in order of occurence.
+    pub fn free_slots(&self) -> Vec<Slot> {
+        let mut out = Vec::new();
+        self.rename_slots(&mut |s| { out.push(s); s }, &mut |s| s);
+        firsts(out)
+    }
+
+    /// Renames the bound slots to `$0`, `$1`, ... in binding order. Free slots keep their names.
+    ///
+    /// Numbers that are already used by free slots are skipped, so that no free slot gets captured.
+    /// Two terms are alpha-equivalent, iff their alpha-normalized versions are equal.
+    pub fn alpha_normalize(&self) -> RecExpr<L> {
+        let free: HashSet<Slot> = self.free_slots().into_iter().collect();
+        let mut next = 0;
+        self.rename_slots(&mut |s| s, &mut |_| {
+            loop {
+                let s = Slot::numeric(next);
+                next += 1;
+                if !free.contains(&s) { return s; }
+            }
+        })
+    }
+
+    /// Like [RecExpr::alpha_normalize], but renames the free slots as well.
+    ///
+    /// The free slots become `$0`, `$1`, ... in order of their first occurence, and the bound slots are numbered after them.
+    pub fn alpha_normalize_all(&self) -> RecExpr<L> {
+        let free = self.free_slots();
+        let m: SlotMap = free.iter().enumerate().map(|(i, s)| (*s, Slot::numeric(i as u32))).collect();
+        let mut next = free.len() as u32;
+        self.rename_slots(&mut |s| m[s], &mut |_| {
+            let s = Slot::numeric(next);
+            next += 1;
+            s
+        })
+    }
+
+    /// Whether both terms are equal up to renaming of their bound slots.
+    pub fn alpha_eq(&self, other: &RecExpr<L>) -> bool {
+        self.alpha_normalize() == other.alpha_normalize()
+    }
+
+    /// Renames the free slots using `m`, and gives fresh names to all binders.
+    pub(crate) fn apply_slotmap_fresh_bound(&self, m: &SlotMap) -> RecExpr<L> {
+        self.rename_slots(&mut |s| m.get(s).unwrap_or(s), &mut |_| Slot::fresh())
+    }
+}
+
+/// A [RecExpr] that is compared and hashed up to renaming of its bound slots.
+///
+/// This allows you to put terms into a HashSet, while treating alpha-equivalent terms as the same.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AlphaRecExpr<L: Language>(RecExpr<L>);
+
+impl<L: Language> AlphaRecExpr<L> {
+    pub fn new(re: &RecExpr<L>) -> Self {
+        AlphaRecExpr(re.alpha_normalize())
+    }
+
+    /// The alpha-normalized term, see [RecExpr::alpha_normalize].
+    pub fn get(&self) -> &RecExpr<L> {
+        &self.0
+    }
+}
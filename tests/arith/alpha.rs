@@ -0,0 +1,29 @@
+use crate::*;
+
+#[test]
+fn alpha_normalize() {
+    let re = RecExpr::<Arith>::parse("(lam $x (add (var $x) (var $y)))").unwrap();
+    assert_eq!(re.alpha_normalize().to_string(), "(lam $0 (add (var $0) (var $y)))");
+    assert_eq!(re.alpha_normalize_all().to_string(), "(lam $1 (add (var $1) (var $0)))");
+
+    // The free $0 must not be captured.
+    let re = RecExpr::<Arith>::parse("(lam $x (add (var $x) (var $0)))").unwrap();
+    assert_eq!(re.alpha_normalize().to_string(), "(lam $1 (add (var $1) (var $0)))");
+
+    let a = RecExpr::<Arith>::parse("(add (lam $a (var $a)) (let $b x (var $b)))").unwrap();
+    let b = RecExpr::<Arith>::parse("(add (lam $c (var $c)) (let $c x (var $c)))").unwrap();
+    let c = RecExpr::<Arith>::parse("(add (lam $c (var $d)) (let $c x (var $c)))").unwrap();
+    assert!(a.alpha_eq(&b));
+    assert!(!a.alpha_eq(&c));
+
+    let set: HashSet<AlphaRecExpr<Arith>> = [&a, &b, &c].into_iter().map(AlphaRecExpr::new).collect();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn alpha_normalize_extracted() {
+    let mut eg = EGraph::<Arith>::new();
+    let i = eg.add_expr(RecExpr::parse("(lam $x (lam $y (add (var $x) (var $y))))").unwrap());
+    let re = extract::<_, _, AstSize>(i, &eg);
+    assert_eq!(re.alpha_normalize().to_string(), "(lam $0 (lam $1 (add (var $0) (var $1))))");
+}
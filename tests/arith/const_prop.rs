@@ -1,22 +1,25 @@
 use crate::*;
 
 #[derive(Clone, PartialEq, Eq, Debug)]
-struct ConstProp(Option<u32>);
+pub struct ConstProp(pub Option<u32>);
 
 impl Analysis<Arith> for ConstProp {
-    fn merge(x: ConstProp, y: ConstProp) -> ConstProp {
+    fn merge(x: &mut ConstProp, y: ConstProp) -> DidMerge {
         match (x.0, y.0) {
             (Some(x), Some(y)) => {
                 assert_eq!(x, y);
-                ConstProp(Some(x))
+                DidMerge(false, false)
             },
-            (Some(x), _) => ConstProp(Some(x)),
-            (_, Some(x)) => ConstProp(Some(x)),
-            (_, _) => ConstProp(None),
+            (Some(_), None) => DidMerge(false, true),
+            (None, Some(y)) => {
+                x.0 = Some(y);
+                DidMerge(true, false)
+            },
+            (None, None) => DidMerge(false, false),
         }
     }
 
-    fn make(eg: &EGraph<Arith, Self>, sh: &Arith) -> ConstProp {
+    fn make<N: Analysis<Arith>>(eg: &AnalysisView<Arith, N, Self>, sh: &Arith) -> ConstProp {
         match sh {
             Arith::Number(x) => ConstProp(Some(*x)),
             Arith::Add(x, y) => {
@@ -34,9 +37,18 @@ impl Analysis<Arith> for ConstProp {
             _ => ConstProp(None),
         }
     }
+
+    fn modify<N: Analysis<Arith>>(eg: &mut AnalysisMut<Arith, N, Self>, i: Id) {
+        if let Some(n) = eg.analysis_data(i).0 {
+            let eg = eg.egraph();
+            let i = AppliedId::new(i, SlotMap::identity(&eg.slots(i)));
+            let c = eg.add(Arith::Number(n));
+            eg.union(&i, &c);
+        }
+    }
 }
 
-fn get_both(eg: &EGraph<Arith, ConstProp>, x: &AppliedId, y: &AppliedId) -> Option<(u32, u32)> {
+fn get_both<N: Analysis<Arith>>(eg: &AnalysisView<Arith, N, ConstProp>, x: &AppliedId, y: &AppliedId) -> Option<(u32, u32)> {
     Some((
         eg.analysis_data(x.id).0?,
         eg.analysis_data(y.id).0?,
@@ -62,3 +74,19 @@ fn const_prop_union() {
 
     assert_eq!(eg.analysis_data(a.id), &ConstProp(Some(42)));
 }
+
+#[test]
+fn const_prop_modify() {
+    let mut eg = EGraph::<Arith, ConstProp>::new();
+    let i = eg.add_expr(RecExpr::parse("(add 2 (mul 2 3))").unwrap());
+    let n = eg.add_expr(RecExpr::parse("8").unwrap());
+    assert!(eg.eq(&i, &n));
+
+    // Constant folding also happens for e-classes that only become constant through a union.
+    let a = eg.add_expr(RecExpr::parse("(mul a 3)").unwrap());
+    let b = eg.add_expr(RecExpr::parse("a").unwrap());
+    let c = eg.add_expr(RecExpr::parse("4").unwrap());
+    eg.union(&b, &c);
+    let n = eg.add_expr(RecExpr::parse("12").unwrap());
+    assert!(eg.eq(&a, &n));
+}
@@ -0,0 +1,50 @@
+use crate::*;
+
+#[test]
+fn dirty_classes() {
+    let mut eg: EGraph<Arith> = EGraph::new();
+    let x = id("x", &mut eg);
+    let y = id("y", &mut eg);
+    let a = id("(add x y)", &mut eg);
+    let b = id("(mul (add x y) y)", &mut eg);
+
+    // Before the first `clear_dirty`, every e-class is dirty.
+    assert_eq!(eg.dirty_classes(0).len(), eg.ids().len());
+    eg.clear_dirty();
+    assert!(eg.dirty_classes(5).is_empty());
+
+    // x gets a new e-node, so its ancestors become dirty too.
+    let z = id("(add x (mul x x))", &mut eg);
+    eg.union(&x, &z);
+    let x = eg.find_applied_id(&x).id;
+    let a = eg.find_applied_id(&a).id;
+    let b = eg.find_applied_id(&b).id;
+    let y = eg.find_applied_id(&y).id;
+
+    let d0 = eg.dirty_classes(0);
+    assert!(d0.contains(&x));
+    assert!(!d0.contains(&a) && !d0.contains(&b) && !d0.contains(&y));
+
+    let d2 = eg.dirty_classes(2);
+    assert!(d2.contains(&x) && d2.contains(&a) && d2.contains(&b));
+    assert!(!d2.contains(&y));
+}
+
+#[test]
+fn dirty_self_symmetry() {
+    let mut eg: EGraph<Arith> = EGraph::new();
+    let a = id("(app (var $0) (var $1))", &mut eg);
+    let b = id("(mul (var $0) (var $1))", &mut eg);
+    let c1 = id("(add (app (var $0) (var $1)) x)", &mut eg);
+    let c2 = id("(add (mul (var $1) (var $0)) x)", &mut eg);
+    eg.union(&c1, &c2);
+    eg.clear_dirty();
+
+    // Afterwards, the e-class of `c1` contains the same e-node twice, up to swapping $0 and $1.
+    eg.union(&a, &b);
+    let swap = SlotMap::from_pairs(&[(Slot::numeric(0), Slot::numeric(1)), (Slot::numeric(1), Slot::numeric(0))]);
+    assert!(eg.eq(&c1, &c1.apply_slotmap(&swap)));
+
+    let c = eg.find_applied_id(&c1).id;
+    assert!(eg.dirty_classes(0).contains(&c));
+}
@@ -0,0 +1,101 @@
+use crate::*;
+
+#[test]
+fn classes_with_op() {
+    let mut eg = EGraph::new();
+    let a = id("(add (var $0) (var $1))", &mut eg);
+    let b = id("(add (var $0) (mul (var $0) (var $1)))", &mut eg);
+    let x = id("(var $0)", &mut eg);
+
+    // The slots and children of the given e-node don't matter.
+    let add = Arith::Add(AppliedId::null(), AppliedId::null());
+    let mut expected = vec![eg.find_applied_id(&a).id, eg.find_applied_id(&b).id];
+    expected.sort();
+    assert_eq!(eg.classes_with_op(&add), expected);
+
+    let var = Arith::Var(Slot::numeric(7));
+    assert_eq!(eg.classes_with_op(&var), vec![eg.find_applied_id(&x).id]);
+
+    // After a union, the index only contains the surviving e-class.
+    eg.union(&a, &b);
+    assert_eq!(eg.classes_with_op(&add), vec![eg.find_applied_id(&a).id]);
+    eg.check();
+}
+
+#[test]
+fn ematch_roots() {
+    let mut eg: EGraph<Arith> = EGraph::new();
+    let a = id("(add (var $5) (var $6))", &mut eg);
+    let b = id("(add x (mul x y))", &mut eg);
+
+    let pat = Pattern::parse("(add ?a ?b)").unwrap();
+    let matches = ematch_all_with_roots(&eg, &pat);
+    assert_eq!(matches.len(), 2);
+    for (root, subst) in matches {
+        // The root is expressed in the same slot names as the subst.
+        let inst = pattern_subst(&mut eg, &pat, &subst);
+        assert!(eg.eq(&root, &inst));
+    }
+
+    // Matching against a single e-class.
+    let pat = Pattern::parse("(add (var $0) ?b)").unwrap();
+    let matches = ematch_applied_id(&eg, &pat, &a);
+    assert_eq!(matches.len(), 1);
+    let (root, subst) = &matches[0];
+    assert!(root.slots().contains(&Slot::numeric(0)));
+    let inst = pattern_subst(&mut eg, &pat, subst);
+    assert!(eg.eq(root, &inst));
+
+    assert!(ematch_applied_id(&eg, &pat, &b).is_empty());
+}
+
+#[test]
+fn pvar_except() {
+    let mut eg: EGraph<Arith> = EGraph::new();
+    id("(add (var $0) (var $1))", &mut eg);
+    id("(add (var $0) (var $0))", &mut eg);
+
+    // `$1` is bound after `?a`, so the check is deferred.
+    let pat = Pattern::parse("(add ?a \\ {$1} (var $1))").unwrap();
+    assert_eq!(ematch_all(&eg, &pat).len(), 1);
+
+    // `$1` is bound before `?a`, so the check is done right away.
+    let pat = Pattern::parse("(add (var $1) ?a \\ {$1})").unwrap();
+    assert_eq!(ematch_all(&eg, &pat).len(), 1);
+
+    // `$1` isn't bound by the pattern at all, so it can't be mentioned by any match.
+    let pat = Pattern::parse("(add ?a \\ {$1} ?b)").unwrap();
+    assert_eq!(ematch_all(&eg, &pat).len(), 2);
+}
+
+#[test]
+fn higher_order_pvar() {
+    // let x = y in b  =>  b[x := y], if b only mentions x.
+    let rw = Rewrite::new("let-rename", "(let $x (var $y) ?b[$x])", "?b[$y]");
+
+    let mut eg: EGraph<Arith> = EGraph::new();
+    let a = id("(let $0 (var $1) (add (var $0) (mul (var $0) (var $0))))", &mut eg);
+    let b = id("(let $0 (var $1) (add (var $0) (var $2)))", &mut eg);
+    apply_rewrites(&mut eg, &[rw]);
+
+    let a2 = id("(add (var $1) (mul (var $1) (var $1)))", &mut eg);
+    assert!(eg.eq(&a, &a2));
+
+    // ?b mentions $2, so it doesn't match.
+    let b2 = id("(add (var $1) (var $2))", &mut eg);
+    assert!(!eg.eq(&b, &b2));
+}
+
+#[test]
+fn higher_order_pvar_twice() {
+    // ?f[$x] and ?f[$y] have to be equal up to renaming $x to $y.
+    let pat = Pattern::parse("(add (lam $x ?f[$x]) (lam $y ?f[$y]))").unwrap();
+
+    let mut eg: EGraph<Arith> = EGraph::new();
+    id("(add (lam $0 (var $0)) (lam $1 (var $1)))", &mut eg);
+    id("(add (lam $0 (var $0)) (lam $1 (add (var $1) (var $1))))", &mut eg);
+
+    let matches = ematch_all(&eg, &pat);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0]["f"].slots(), [Slot::numeric(0)].into_iter().collect());
+}
@@ -0,0 +1,261 @@
+use crate::*;
+
+#[test]
+fn dag_extraction() {
+    let mut eg = EGraph::<Arith>::new();
+    let a = eg.add_expr(RecExpr::parse("(add (mul a (mul b c)) (mul a (mul b c)))").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(mul (add a b) (add c d))").unwrap());
+    eg.union(&a, &b);
+
+    // As a tree, the shared subterm is paid for twice.
+    assert_eq!(extract::<_, _, AstSize>(a.clone(), &eg).to_string(), "(mul (add a b) (add c d))");
+
+    let greedy = dag_extract(a.clone(), &eg, &AstSize).unwrap();
+    assert_eq!(greedy.cost, 6);
+    assert_eq!(greedy.to_recexpr().to_string(), "(add (mul a (mul b c)) (mul a (mul b c)))");
+
+    let exact = dag_extract_exact(a, &eg, &AstSize).unwrap();
+    assert_eq!(exact.cost, 6);
+    assert_eq!(exact.choices.len(), 6);
+}
+
+#[test]
+fn dag_extraction_exact() {
+    // On its own, (add s t) is cheaper than (add ?p ?p), but the latter can share ?p with the rest of the term.
+    let mut eg = EGraph::<Arith>::new();
+    let a1 = eg.add_expr(RecExpr::parse("(add s t)").unwrap());
+    let a2 = eg.add_expr(RecExpr::parse("(add (mul x (mul y z)) (mul x (mul y z)))").unwrap());
+    eg.union(&a1, &a2);
+    let root = eg.add_expr(RecExpr::parse("(add (add s t) (mul (mul x (mul y z)) w))").unwrap());
+
+    let greedy = dag_extract(root.clone(), &eg, &AstSize).unwrap();
+    assert_eq!(greedy.cost, 11);
+
+    let exact = dag_extract_exact(root, &eg, &AstSize).unwrap();
+    assert_eq!(exact.cost, 9);
+    assert_eq!(exact.to_recexpr().to_string(), "(add (add (mul x (mul y z)) (mul x (mul y z))) (mul (mul x (mul y z)) w))");
+}
+
+#[test]
+fn top_k_extraction() {
+    let mut eg = EGraph::<Arith>::new();
+    let x = x_plus_zero(&mut eg);
+
+    let extractor = TopKExtractor::<_, AstSize>::new(&eg, AstSize, 3);
+    let terms: Vec<(String, u64)> = extractor.extract(x, &eg).into_iter().map(|(t, c)| (t.to_string(), c)).collect();
+    assert_eq!(terms, vec![
+        (String::from("x"), 1),
+        (String::from("(add x 0)"), 3),
+        (String::from("(add (add x 0) 0)"), 5),
+    ]);
+}
+
+#[test]
+fn top_k_extraction_alpha_eq() {
+    let mut eg = EGraph::<Arith>::new();
+    let a = eg.add_expr(RecExpr::parse("(lam $0 (add (var $0) x))").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(lam $1 (add x (var $1)))").unwrap());
+    eg.union(&a, &b);
+
+    // Each term is built again with fresh binders whenever the extractor revisits the e-class.
+    // These copies shouldn't count as different terms.
+    let terms = TopKExtractor::<_, AstSize>::new(&eg, AstSize, 5).extract(a.clone(), &eg);
+    assert_eq!(terms.len(), 2);
+    assert!(terms.iter().all(|(_, c)| *c == 4));
+
+    let terms = TopKExtractor::<_, AstSize>::new(&eg, AstSize, 1).extract(a, &eg);
+    assert_eq!(terms.len(), 1);
+}
+
+#[test]
+fn top_k_extraction_repeated_child() {
+    let mut eg = EGraph::<Arith>::new();
+    let c = eg.add_expr(RecExpr::parse("x").unwrap());
+    let c2 = eg.add_expr(RecExpr::parse("(add y 0)").unwrap());
+    eg.union(&c, &c2);
+    let a = eg.add_expr(RecExpr::parse("(mul x x)").unwrap());
+
+    // Both occurences of `c` pick their terms independently.
+    let terms: Vec<(String, u64)> = TopKExtractor::<_, AstSize>::new(&eg, AstSize, 4).extract(a, &eg)
+        .into_iter().map(|(t, c)| (t.to_string(), c)).collect();
+    assert_eq!(terms, vec![
+        (String::from("(mul x x)"), 3),
+        (String::from("(mul x (add y 0))"), 5),
+        (String::from("(mul (add y 0) x)"), 5),
+        (String::from("(mul (add y 0) (add y 0))"), 7),
+    ]);
+}
+
+#[test]
+fn try_extract_unknown_class() {
+    let mut eg = EGraph::<Arith>::new();
+    let x = x_plus_zero(&mut eg);
+
+    let extractor = Extractor::<_, AstSize>::new(&eg, AstSize);
+    assert!(extractor.unextractable().is_empty());
+    assert_eq!(extractor.try_extract(x, &eg).unwrap().to_string(), "x");
+
+    // The extractor doesn't know about e-classes created after it.
+    let y = eg.add_expr(RecExpr::parse("y").unwrap());
+    assert_eq!(extractor.try_extract(y.clone(), &eg), Err(ExtractionError::Unextractable(y.id)));
+}
+
+#[test]
+fn refresh_extractor() {
+    fn size(re: &RecExpr<Arith>) -> usize {
+        1 + re.children.iter().map(size).sum::<usize>()
+    }
+
+    let mut eg = EGraph::<Arith>::new();
+    eg.add_expr(RecExpr::parse("(app (lam $0 (add (var $0) (mul 2 (add x 0)))) (mul (add x 0) 1))").unwrap());
+
+    let mut extractor = Extractor::<_, AstSize>::new(&eg, AstSize);
+    for _ in 0..3 {
+        rewrite_arith(&mut eg);
+        x_plus_zero(&mut eg);
+
+        extractor.refresh(&eg);
+        let fresh = Extractor::<_, AstSize>::new(&eg, AstSize);
+        assert!(extractor.unextractable().is_empty());
+        for i in class_ids(&eg) {
+            assert_eq!(size(&extractor.extract(i.clone(), &eg)), size(&fresh.extract(i, &eg)));
+        }
+    }
+}
+
+#[test]
+fn extract_with_lets() {
+    let mk_let = |x, t, b| RecExpr { node: Arith::Let(x, AppliedId::null(), AppliedId::null()), children: vec![t, b] };
+    let mk_var = |x| RecExpr { node: Arith::Var(x), children: vec![] };
+
+    let mut eg = EGraph::<Arith>::new();
+    let i = eg.add_expr(RecExpr::parse("(mul (add x y) (add x y))").unwrap());
+    let extractor = Extractor::<_, AstSize>::new(&eg, AstSize);
+    let re = extractor.extract_with_lets(i, &eg, mk_let, mk_var).unwrap();
+    assert_eq!(re.alpha_normalize().to_string(), "(let $0 (add x y) (mul (var $0) (var $0)))");
+
+    // The inner let can't leave the lambda, as it uses its variable.
+    let mut eg = EGraph::<Arith>::new();
+    let i = eg.add_expr(RecExpr::parse("(add (lam $1 (mul (add (var $1) y) (add (var $1) y))) (lam $2 (mul (add (var $2) y) (add (var $2) y))))").unwrap());
+    let extractor = Extractor::<_, AstSize>::new(&eg, AstSize);
+    let re = extractor.extract_with_lets(i, &eg, mk_let, mk_var).unwrap();
+    assert_eq!(re.alpha_normalize().to_string(), "(let $0 (lam $1 (let $2 (add (var $1) y) (mul (var $2) (var $2)))) (add (var $0) (var $0)))");
+
+    // The shared term uses the outer variable, so it is bound within the outer lambda, above both of its uses.
+    let mut eg = EGraph::<Arith>::new();
+    let i = eg.add_expr(RecExpr::parse("(lam $1 (add (lam $2 (mul (add (var $1) 1) (var $2))) (lam $3 (add (add (var $1) 1) (var $3)))))").unwrap());
+    let extractor = Extractor::<_, AstSize>::new(&eg, AstSize);
+    let re = extractor.extract_with_lets(i, &eg, mk_let, mk_var).unwrap();
+    assert_eq!(re.alpha_normalize().to_string(), "(lam $0 (let $1 (add (var $0) 1) (add (lam $2 (mul (var $1) (var $2))) (lam $3 (add (var $1) (var $3))))))");
+
+    // Both lambdas share the same body, but each of them uses it only once, so only the inner term is bound.
+    let mut eg = EGraph::<Arith>::new();
+    let i = eg.add_expr(RecExpr::parse("(add (lam $1 (add (var $3) (mul (add (var $1) 1) (add (var $1) 1)))) (lam $1 (add (var $4) (mul (add (var $1) 1) (add (var $1) 1)))))").unwrap());
+    let extractor = Extractor::<_, AstSize>::new(&eg, AstSize);
+    let re = extractor.extract_with_lets(i, &eg, mk_let, mk_var).unwrap();
+    assert_eq!(re.alpha_normalize().to_string(), "(add (lam $0 (add (var $3) (let $1 (add (var $0) 1) (mul (var $1) (var $1))))) (lam $2 (add (var $4) (let $5 (add (var $2) 1) (mul (var $5) (var $5))))))");
+}
+
+#[test]
+fn pareto_extraction() {
+    // counts the additions and the multiplications.
+    struct AddsMuls;
+
+    impl ParetoCostFunction<Arith> for AddsMuls {
+        type Cost = ParetoPair<u64, u64>;
+
+        fn cost<C>(&self, enode: &Arith, costs: C) -> Self::Cost where C: Fn(Id) -> Self::Cost {
+            let mut s = match enode {
+                Arith::Add(..) => ParetoPair(1, 0),
+                Arith::Mul(..) => ParetoPair(0, 1),
+                _ => ParetoPair(0, 0),
+            };
+            for x in enode.applied_id_occurences() {
+                let c = costs(x.id);
+                s = ParetoPair(s.0 + c.0, s.1 + c.1);
+            }
+            s
+        }
+    }
+
+    let mut eg = EGraph::<Arith>::new();
+    let a = eg.add_expr(RecExpr::parse("(mul x 2)").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(add x x)").unwrap());
+    let c = eg.add_expr(RecExpr::parse("(mul x (add 1 1))").unwrap());
+    let two = eg.add_expr(RecExpr::parse("2").unwrap());
+    let one_one = eg.add_expr(RecExpr::parse("(add 1 1)").unwrap());
+    eg.union(&a, &b);
+    eg.union(&two, &one_one);
+    let i = eg.add_expr(RecExpr::parse("(add y (mul x 2))").unwrap());
+    assert!(eg.eq(&a, &c));
+
+    let ex = ParetoExtractor::new(&eg, AddsMuls);
+    let mut frontier: Vec<(String, ParetoPair<u64, u64>)> = ex.extract(a, &eg).into_iter().map(|(t, c)| (t.to_string(), c)).collect();
+    frontier.sort_by_key(|(_, c)| c.0);
+    assert_eq!(frontier, vec![("(mul x 2)".to_string(), ParetoPair(0, 1)), ("(add x x)".to_string(), ParetoPair(1, 0))]);
+
+    let mut costs: Vec<ParetoPair<u64, u64>> = ex.extract(i, &eg).into_iter().map(|(_, c)| c).collect();
+    costs.sort_by_key(|c| c.0);
+    assert_eq!(costs, vec![ParetoPair(1, 1), ParetoPair(2, 0)]);
+
+    // The terms are built in `extract`, and keep the free slots of the e-class.
+    let j = eg.add_expr(RecExpr::parse("(lam $1 (add (var $1) (mul (var $2) 0)))").unwrap());
+    let frontier = ParetoExtractor::new(&eg, AddsMuls).extract(j, &eg);
+    assert_eq!(frontier.len(), 1);
+    assert_eq!(frontier[0].0.alpha_normalize().to_string(), "(lam $0 (add (var $0) (mul (var $2) 0)))");
+}
+
+#[test]
+fn pareto_extraction_repeated_child() {
+    // An e-node costs the component-wise minimum of its children.
+    struct MinCost;
+
+    impl ParetoCostFunction<Arith> for MinCost {
+        type Cost = ParetoPair<u64, u64>;
+
+        fn cost<C>(&self, enode: &Arith, costs: C) -> Self::Cost where C: Fn(Id) -> Self::Cost {
+            if let Arith::Number(n) = enode {
+                return ParetoPair(*n as u64, 2u64.saturating_sub(*n as u64));
+            }
+            enode.applied_id_occurences().iter()
+                 .map(|x| costs(x.id))
+                 .fold(ParetoPair(2, 2), |a, c| ParetoPair(a.0.min(c.0), a.1.min(c.1)))
+        }
+    }
+
+    let mut eg = EGraph::<Arith>::new();
+    let a = eg.add_expr(RecExpr::parse("0").unwrap());
+    let b = eg.add_expr(RecExpr::parse("2").unwrap());
+    eg.union(&a, &b);
+    let i = eg.add_expr(RecExpr::parse("(mul 0 0)").unwrap());
+
+    // Only a term using both 0 and 2 reaches the cost (0, 0), and it dominates all other terms.
+    let frontier: Vec<(String, ParetoPair<u64, u64>)> = ParetoExtractor::new(&eg, MinCost).extract(i, &eg)
+        .into_iter().map(|(t, c)| (t.to_string(), c)).collect();
+    assert_eq!(frontier.len(), 1);
+    assert_eq!(frontier[0].1, ParetoPair(0, 0));
+    assert!(frontier[0].0 == "(mul 0 2)" || frontier[0].0 == "(mul 2 0)");
+}
+
+#[test]
+fn sketch_extraction() {
+    let mut eg = EGraph::<Arith>::new();
+    let a = eg.add_expr(RecExpr::parse("(mul (add x y) 2)").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(add (mul x 2) (mul y 2))").unwrap());
+    eg.union(&a, &b);
+
+    assert_eq!(ast_size_extract(a.clone(), &eg).to_string(), "(mul (add x y) 2)");
+
+    let sketch = Pattern::parse("(add ?a ?b)").unwrap();
+    let re = sketch_extract::<_, _, AstSize>(a.clone(), &sketch, &eg).unwrap();
+    assert_eq!(re.to_string(), "(add (mul x 2) (mul y 2))");
+
+    let sketch = Pattern::parse("(lam $1 ?b)").unwrap();
+    assert!(sketch_extract::<_, _, AstSize>(a, &sketch, &eg).is_none());
+
+    // The free slots of the result are the ones of `i`, the binders of the sketch get fresh names.
+    let i = eg.add_expr(RecExpr::parse("(lam $x (add (var $x) (var $y)))").unwrap());
+    let sketch = Pattern::parse("(lam $1 (add ?a ?b))").unwrap();
+    let re = sketch_extract::<_, _, AstSize>(i, &sketch, &eg).unwrap();
+    assert_eq!(re.alpha_normalize().to_string(), "(lam $0 (add (var $0) (var $y)))");
+}
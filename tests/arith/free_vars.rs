@@ -0,0 +1,146 @@
+use crate::*;
+
+use std::collections::BTreeSet;
+
+// The set of variables that are actually used by a term.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FreeVars(pub BTreeSet<Slot>);
+
+impl Analysis<Arith> for FreeVars {
+    fn merge(x: &mut FreeVars, y: FreeVars) -> DidMerge {
+        // Equal terms only need the variables that both of them use.
+        let did = DidMerge(!x.0.is_subset(&y.0), !y.0.is_subset(&x.0));
+        x.0.retain(|s| y.0.contains(s));
+        did
+    }
+
+    fn make<N: Analysis<Arith>>(eg: &AnalysisView<Arith, N, Self>, enode: &Arith) -> FreeVars {
+        let fv = |x: &AppliedId| eg.analysis_data_applied(x).0;
+        let out = match enode {
+            Arith::Var(s) => [*s].into_iter().collect(),
+            Arith::Lam(s, b) => &fv(b) - &[*s].into_iter().collect(),
+            Arith::Let(s, t, b) => &fv(t) | &(&fv(b) - &[*s].into_iter().collect()),
+            Arith::App(x, y) | Arith::Add(x, y) | Arith::Mul(x, y) => &fv(x) | &fv(y),
+            Arith::Number(_) | Arith::Symbol(_) => BTreeSet::new(),
+        };
+        FreeVars(out)
+    }
+
+    fn apply_slotmap(&self, m: &SlotMap) -> FreeVars {
+        FreeVars(self.0.iter().filter_map(|s| m.get(*s)).collect())
+    }
+}
+
+fn free_vars(s: &str, eg: &mut EGraph<Arith, FreeVars>) -> BTreeSet<Slot> {
+    let i = eg.add_expr(RecExpr::parse(s).unwrap());
+    eg.analysis_data_applied(&i).0
+}
+
+fn slots(s: &[u32]) -> BTreeSet<Slot> {
+    s.iter().map(|x| Slot::numeric(*x)).collect()
+}
+
+#[test]
+fn free_vars_renamed() {
+    let mut eg = EGraph::<Arith, FreeVars>::new();
+    assert_eq!(free_vars("(add (var $0) (var $1))", &mut eg), slots(&[0, 1]));
+
+    // This is the same e-class as above, but the analysis data uses our slot names.
+    assert_eq!(free_vars("(add (var $3) (var $4))", &mut eg), slots(&[3, 4]));
+
+    assert_eq!(free_vars("(lam $0 (add (var $0) (var $1)))", &mut eg), slots(&[1]));
+    assert_eq!(free_vars("(let $0 (var $2) (mul (var $0) (var $1)))", &mut eg), slots(&[1, 2]));
+}
+
+#[test]
+fn free_vars_union() {
+    let mut eg = EGraph::<Arith, FreeVars>::new();
+    let a = eg.add_expr(RecExpr::parse("(add (var $0) (var $1))").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(mul (var $1) (var $0))").unwrap());
+    eg.union(&a, &b);
+
+    // Both e-classes used their own slots, so the merge had to rename them.
+    assert_eq!(eg.analysis_data_applied(&a).0, slots(&[0, 1]));
+    assert_eq!(eg.analysis_data_applied(&b).0, slots(&[0, 1]));
+}
+
+#[test]
+fn free_vars_with_const_prop() {
+    let mut eg = EGraph::<Arith, (ConstProp, FreeVars)>::new();
+    let a = eg.add_expr(RecExpr::parse("(add (var $0) (mul 2 3))").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(add 2 3)").unwrap());
+
+    assert_eq!(eg.analysis_data(a.id).get::<ConstProp>(), Some(&ConstProp(None)));
+    assert_eq!(eg.analysis_data_applied(&a).1.0, slots(&[0]));
+    assert_eq!(eg.analysis_data(b.id).0, ConstProp(Some(5)));
+    assert_eq!(eg.analysis_data(b.id).get::<FreeVars>(), Some(&FreeVars(BTreeSet::new())));
+
+    // ConstProp still folds constants within the tuple.
+    let five = eg.add_expr(RecExpr::parse("5").unwrap());
+    assert!(eg.eq(&b, &five));
+
+    // Each component is merged on its own.
+    let c = eg.add_expr(RecExpr::parse("(var $1)").unwrap());
+    eg.union(&b, &c);
+    assert_eq!(eg.analysis_data(b.id).0, ConstProp(Some(5)));
+    assert_eq!(eg.analysis_data(b.id).1, FreeVars(BTreeSet::new()));
+}
+
+// AstSize, but lets that don't use their variable are forbidden.
+#[derive(Default)]
+struct NoUnusedLet;
+
+impl AnalysisCostFunction<Arith, FreeVars> for NoUnusedLet {
+    type Cost = u64;
+
+    fn cost<C>(&self, eg: &EGraph<Arith, FreeVars>, enode: &Arith, costs: C) -> u64 where C: Fn(Id) -> u64 {
+        if let Arith::Let(x, _, b) = enode {
+            if !eg.analysis_data_applied(b).0.contains(x) {
+                return u64::MAX;
+            }
+        }
+        let mut s: u64 = 1;
+        for x in enode.applied_id_occurences() {
+            s = s.saturating_add(costs(x.id));
+        }
+        s
+    }
+}
+
+#[test]
+fn analysis_cost_function() {
+    let mut eg = EGraph::<Arith, FreeVars>::new();
+    let a = eg.add_expr(RecExpr::parse("(let $0 5 (add (var $2) (var $2)))").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(mul (var $2) (add 1 (add 1 1)))").unwrap());
+    eg.union(&a, &b);
+
+    // The bound slot of the let is fresh, so we don't compare strings here.
+    assert!(matches!(extract::<_, _, AstSize>(a.clone(), &eg).node, Arith::Let(..)));
+    assert_eq!(extract::<_, _, NoUnusedLet>(a.clone(), &eg).to_string(), "(mul (var $2) (add 1 (add 1 1)))");
+
+    // The analysis of the e-graph is inferred.
+    let ex = Extractor::<_, AstSize>::new(&eg, AstSize);
+    assert!(matches!(ex.extract(a.clone(), &eg).node, Arith::Let(..)));
+    let ex = Extractor::new(&eg, NoUnusedLet);
+    assert_eq!(ex.extract(a.clone(), &eg).to_string(), "(mul (var $2) (add 1 (add 1 1)))");
+    let terms = TopKExtractor::<_, AstSize>::new(&eg, AstSize, 2).extract(a, &eg);
+    assert_eq!(terms.len(), 2);
+}
+
+#[test]
+fn analysis_cost_function_sketch() {
+    let mut eg = EGraph::<Arith, FreeVars>::new();
+    let a = eg.add_expr(RecExpr::parse("(let $0 5 (add (var $2) (var $2)))").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(mul (var $2) (add 1 (add 1 1)))").unwrap());
+    eg.union(&a, &b);
+
+    // The sketch forces the let, and its cost is computed from the analysis data of the real e-classes.
+    let sketch = Pattern::parse("(let $0 ?t ?b)").unwrap();
+    let re = sketch_extract::<_, _, NoUnusedLet>(a.clone(), &sketch, &eg).unwrap();
+    assert!(matches!(re.node, Arith::Let(..)));
+    assert_eq!(re.children[1].to_string(), "(add (var $2) (var $2))");
+
+    let sketch = Pattern::parse("(mul ?x ?y)").unwrap();
+    let re = sketch_extract::<_, _, NoUnusedLet>(a, &sketch, &eg).unwrap();
+    assert_eq!(re.to_string(), "(mul (var $2) (add 1 (add 1 1)))");
+}
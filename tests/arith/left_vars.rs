@@ -0,0 +1,49 @@
+use crate::*;
+
+use std::collections::BTreeSet;
+
+// The variables that occur as the left-most leaf of some term in an e-class.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LeftVars(pub BTreeSet<Slot>);
+
+impl Analysis<Arith> for LeftVars {
+    fn merge(x: &mut LeftVars, y: LeftVars) -> DidMerge {
+        let did = DidMerge(!y.0.is_subset(&x.0), !x.0.is_subset(&y.0));
+        x.0.extend(y.0);
+        did
+    }
+
+    fn make<N: Analysis<Arith>>(eg: &AnalysisView<Arith, N, Self>, enode: &Arith) -> LeftVars {
+        let lv = |x: &AppliedId| eg.analysis_data_applied(x).0;
+        let out = match enode {
+            Arith::Var(s) => [*s].into_iter().collect(),
+            Arith::Lam(s, b) => &lv(b) - &[*s].into_iter().collect(),
+            Arith::App(x, _) | Arith::Add(x, _) | Arith::Mul(x, _) | Arith::Let(_, x, _) => lv(x),
+            Arith::Number(_) | Arith::Symbol(_) => BTreeSet::new(),
+        };
+        LeftVars(out)
+    }
+
+    fn apply_slotmap(&self, m: &SlotMap) -> LeftVars {
+        LeftVars(self.0.iter().filter_map(|s| m.get(*s)).collect())
+    }
+}
+
+fn slots(s: &[u32]) -> BTreeSet<Slot> {
+    s.iter().map(|x| Slot::numeric(*x)).collect()
+}
+
+#[test]
+fn left_vars_self_symmetry() {
+    let mut eg = EGraph::<Arith, LeftVars>::new();
+    let a = eg.add_expr(RecExpr::parse("(add (var $0) (var $1))").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(add (var $1) (var $0))").unwrap());
+    assert_eq!(eg.analysis_data_applied(&a).0, slots(&[0]));
+    assert_eq!(eg.analysis_data_applied(&b).0, slots(&[1]));
+
+    // This only adds a self-symmetry to the e-class, and the analysis data has to respect it.
+    eg.union(&a, &b);
+    assert!(eg.eq(&a, &b));
+    assert_eq!(eg.analysis_data_applied(&a).0, slots(&[0, 1]));
+    assert_eq!(eg.analysis_data_applied(&b).0, slots(&[0, 1]));
+}
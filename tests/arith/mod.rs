@@ -6,6 +6,24 @@ use crate::*;
 mod tst;
 pub use tst::*;
 
+mod runner;
+pub use runner::*;
+
+mod ematch;
+pub use ematch::*;
+
+mod dirty;
+pub use dirty::*;
+
+mod extract;
+pub use extract::*;
+
+mod alpha;
+pub use alpha::*;
+
+mod terms;
+pub use terms::*;
+
 mod rewrite;
 pub use rewrite::*;
 
@@ -15,6 +33,12 @@ pub use my_cost::*;
 mod const_prop;
 pub use const_prop::*;
 
+mod free_vars;
+pub use free_vars::*;
+
+mod left_vars;
+pub use left_vars::*;
+
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Arith {
     // lambda calculus:
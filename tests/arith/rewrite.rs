@@ -20,7 +20,7 @@ pub fn rewrite_arith(eg: &mut EGraph<Arith>) {
 
         distr1(),
         distr2(),
-    ])
+    ]);
 }
 
 fn beta() -> Rewrite<Arith> {
@@ -87,13 +87,13 @@ fn mul_comm() -> Rewrite<Arith> {
     Rewrite::new("mul-comm", pat, outpat)
 }
 
-fn add_assoc1() -> Rewrite<Arith> {
+pub fn add_assoc1() -> Rewrite<Arith> {
     let pat = "(add ?a (add ?b ?c))";
     let outpat = "(add (add ?a ?b) ?c)";
     Rewrite::new("add-assoc1", pat, outpat)
 }
 
-fn add_assoc2() -> Rewrite<Arith> {
+pub fn add_assoc2() -> Rewrite<Arith> {
     let pat = "(add (add ?a ?b) ?c)";
     let outpat = "(add ?a (add ?b ?c))";
     Rewrite::new("add-assoc2", pat, outpat)
@@ -0,0 +1,122 @@
+use crate::*;
+
+use std::time::Duration;
+
+#[test]
+fn runner_saturates() {
+    let mut eg = EGraph::new();
+    eg.add_expr(RecExpr::parse("(add a b)").unwrap());
+
+    let runner = Runner::new(eg).run(&[add_comm()]);
+    assert_eq!(runner.stop_reason, Some(StopReason::Saturated));
+    assert_eq!(runner.iterations.len(), 2);
+}
+
+#[test]
+fn runner_iter_limit() {
+    let mut eg = EGraph::new();
+    eg.add_expr(RecExpr::parse("(add a b)").unwrap());
+
+    let runner = Runner::new(eg).with_iter_limit(1).run(&[add_comm()]);
+    assert_eq!(runner.stop_reason, Some(StopReason::IterationLimit(1)));
+    assert_eq!(runner.iterations.len(), 1);
+}
+
+#[test]
+fn apply_rewrites_reports_changes() {
+    let mut eg = EGraph::new();
+    eg.add_expr(RecExpr::parse("(add a b)").unwrap());
+
+    let changes = apply_rewrites(&mut eg, &[add_comm()]);
+    assert_eq!(changes, Changes { unions: 1, new_enodes: 1 });
+
+    let changes = apply_rewrites(&mut eg, &[add_comm()]);
+    assert!(changes.is_empty());
+}
+
+#[test]
+fn apply_rewrites_reports_symmetry() {
+    // (add x y) = (add y x) doesn't add an e-node, it only adds a symmetry to the e-class.
+    let mut eg = EGraph::new();
+    eg.add_expr(RecExpr::parse("(add (var $0) (var $1))").unwrap());
+
+    let changes = apply_rewrites(&mut eg, &[add_comm()]);
+    assert_eq!(changes, Changes { unions: 1, new_enodes: 0 });
+
+    let changes = apply_rewrites(&mut eg, &[add_comm()]);
+    assert!(changes.is_empty());
+}
+
+#[test]
+fn backoff_bans_rule() {
+    let mut eg = EGraph::new();
+    eg.add_expr(RecExpr::parse("(add a b)").unwrap());
+
+    // add-comm has one match, which is above the match limit of 0. So it never gets applied.
+    let scheduler = BackoffScheduler::default().with_initial_match_limit(0);
+    let runner = Runner::new(eg).with_iter_limit(5).with_scheduler(scheduler).run(&[add_comm()]);
+    assert_eq!(runner.stop_reason, Some(StopReason::IterationLimit(5)));
+    assert_eq!(runner.egraph.total_number_of_nodes(), 3);
+}
+
+#[test]
+fn runner_report() {
+    let mut eg = EGraph::new();
+    eg.add_expr(RecExpr::parse("(add a b)").unwrap());
+
+    let rules = [add_comm()];
+    assert_eq!(rules[0].name(), "add-comm");
+
+    let report = Runner::new(eg).run(&rules).report();
+    assert_eq!(report.iterations.len(), 2);
+
+    let stats = &report.iterations[0].rewrites[0];
+    assert_eq!(stats.name, "add-comm");
+    assert_eq!(stats.matches, 1);
+    assert_eq!(stats.changes, Changes { unions: 1, new_enodes: 1 });
+
+    let table = report.to_string();
+    let lines: Vec<Vec<&str>> = table.lines().map(|l| l.split_whitespace().collect()).collect();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0][..3], ["iter", "rewrite", "matches"]);
+    // The search and apply times vary, so only check the counts.
+    assert_eq!(lines[1][..5], ["0", "add-comm", "1", "1", "1"]);
+    assert_eq!(lines[1][7..], ["4", "3"]);
+    assert_eq!(lines[2][..5], ["1", "add-comm", "2", "0", "0"]);
+    assert_eq!(lines[3], ["stop", "reason:", "Saturated"]);
+    assert_eq!(lines[4][..2], ["total", "time:"]);
+
+    let json = report.to_json();
+    assert!(json.starts_with("{\"stop_reason\": \"Saturated\""));
+    assert!(json.contains("\"name\": \"add-comm\", \"matches\": 1, \"applied\": true, \"unions\": 1, \"new_enodes\": 1"));
+}
+
+#[test]
+fn incremental_search() {
+    // Incremental search skips the matches that have already been applied,
+    // so it should result in the same e-graph as searching fully each iteration.
+    let run = |incremental: bool| {
+        let mut eg = EGraph::new();
+        eg.add_expr(RecExpr::parse("(add (add (add a b) c) d)").unwrap());
+
+        let mut runner = Runner::new(eg).with_iter_limit(100).with_node_limit(usize::MAX).with_time_limit(Duration::MAX);
+        if incremental {
+            runner = runner.with_incremental_search();
+        }
+        runner.run(&[add_comm(), add_assoc1(), add_assoc2()])
+    };
+
+    let full = run(false);
+    let incr = run(true);
+    assert_eq!(full.stop_reason, Some(StopReason::Saturated));
+    assert_eq!(incr.stop_reason, Some(StopReason::Saturated));
+
+    let sizes = |r: &Runner<Arith>| r.iterations.iter().map(|it| (it.egraph_nodes, it.egraph_classes)).collect::<Vec<_>>();
+    assert_eq!(sizes(&full), sizes(&incr));
+
+    // The incremental search never finds more matches, and in total it finds less.
+    let matches = |r: &Runner<Arith>| r.iterations.iter().map(|it| it.rewrites.iter().map(|rw| rw.matches).sum::<usize>()).collect::<Vec<_>>();
+    let (m_full, m_incr) = (matches(&full), matches(&incr));
+    assert!(m_full.iter().zip(&m_incr).all(|(f, i)| i <= f));
+    assert!(m_incr.iter().sum::<usize>() < m_full.iter().sum::<usize>());
+}
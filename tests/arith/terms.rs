@@ -0,0 +1,101 @@
+use crate::*;
+
+#[test]
+fn enumerate_and_count_terms() {
+    let mut eg = EGraph::<Arith>::new();
+    let x = x_plus_zero(&mut eg);
+
+    let terms: Vec<String> = enumerate_terms(x.clone(), &eg, 5).iter().map(|t| t.to_string()).collect();
+    assert_eq!(terms, vec!["x", "(add x 0)", "(add (add x 0) 0)"]);
+
+    let counts = count_terms(&eg, 5);
+    assert_eq!(counts[&x.id], 3);
+    for i in class_ids(&eg) {
+        assert_eq!(counts[&i.id], enumerate_terms(i, &eg, 5).len() as u64);
+    }
+
+    // Bound slots get fresh names, free slots are taken from the AppliedId.
+    let mut eg = EGraph::<Arith>::new();
+    let i = eg.add_expr(RecExpr::parse("(lam $x (add (var $x) (var $y)))").unwrap());
+    let terms = enumerate_terms(i, &eg, 10);
+    assert_eq!(terms.len(), 1);
+    assert_eq!(terms[0].alpha_normalize().to_string(), "(lam $0 (add (var $0) (var $y)))");
+}
+
+#[test]
+fn count_terms_binders_and_symmetries() {
+    let cases = [
+        // a symmetric e-class: the swapped term is the same e-node, so it's not counted again.
+        ("(add (var $x) (var $y))", "(add (var $y) (var $x))", 1),
+        // two binders, which are swapped by the second term.
+        ("(lam $x (lam $y (add (var $x) (var $y))))", "(lam $y (lam $x (add (var $x) (var $y))))", 2),
+        // lets that introduce new binders in every step.
+        ("(let $x (var $y) (var $x))", "(var $y)", 9),
+        // a redundant slot.
+        ("(mul (var $x) 0)", "0", 4),
+    ];
+    for (a, b, n) in cases {
+        let mut eg = EGraph::<Arith>::new();
+        let a = eg.add_expr(RecExpr::parse(a).unwrap());
+        let b = eg.add_expr(RecExpr::parse(b).unwrap());
+        eg.union(&a, &b);
+
+        let counts = count_terms(&eg, 7);
+        assert_eq!(counts[&eg.find_applied_id(&a).id], n);
+        for i in class_ids(&eg) {
+            assert_eq!(counts[&i.id], enumerate_terms(i, &eg, 7).len() as u64);
+        }
+    }
+}
+
+#[test]
+fn sample_terms() {
+    use rand::{Rng, SeedableRng};
+
+    let mut r = rand::rngs::StdRng::seed_from_u64(0);
+    let mut rng = || r.gen::<u64>();
+
+    let mut eg = EGraph::<Arith>::new();
+    let x = x_plus_zero(&mut eg);
+
+    let all: Vec<String> = enumerate_terms(x.clone(), &eg, 5).iter().map(|t| t.to_string()).collect();
+    let sampler = TermSampler::new(&eg, 5);
+    let mut seen = HashSet::default();
+    for _ in 0..100 {
+        let t = sampler.sample(x.clone(), &eg, &mut rng).unwrap().to_string();
+        assert!(all.contains(&t));
+        seen.insert(t);
+    }
+    assert_eq!(seen.len(), all.len());
+
+    // e-nodes with weight zero are never chosen.
+    for _ in 0..10 {
+        let t = sampler.sample_weighted(x.clone(), &eg, |n| if let Arith::Add(..) = n { 0.0 } else { 1.0 }, &mut rng).unwrap();
+        assert_eq!(t.to_string(), "x");
+    }
+
+    // the binders are fresh in every sample.
+    let mut eg = EGraph::<Arith>::new();
+    let i = eg.add_expr(RecExpr::parse("(lam $x (add (var $x) (var $y)))").unwrap());
+    let sampler = TermSampler::new(&eg, 10);
+    let a = sampler.sample(i.clone(), &eg, &mut rng).unwrap();
+    let b = sampler.sample(i, &eg, &mut rng).unwrap();
+    assert_ne!(a, b);
+    assert!(a.alpha_eq(&b));
+    assert_eq!(a.free_slots(), vec![Slot::named("y")]);
+
+    // The samples hit exactly the terms of enumerate_terms, also with binders and symmetries.
+    let mut eg = EGraph::<Arith>::new();
+    let a = eg.add_expr(RecExpr::parse("(lam $x (lam $y (add (var $x) (var $y))))").unwrap());
+    let b = eg.add_expr(RecExpr::parse("(lam $y (lam $x (add (var $x) (var $y))))").unwrap());
+    eg.union(&a, &b);
+    let all: HashSet<AlphaRecExpr<Arith>> = enumerate_terms(a.clone(), &eg, 7).iter().map(AlphaRecExpr::new).collect();
+    let sampler = TermSampler::new(&eg, 7);
+    let seen: HashSet<AlphaRecExpr<Arith>> = (0..50).map(|_| AlphaRecExpr::new(&sampler.sample(a.clone(), &eg, &mut rng).unwrap())).collect();
+    assert_eq!(seen, all);
+
+    // e-classes created after the sampler have no samples.
+    let c = eg.add_expr(RecExpr::parse("(add 1 2)").unwrap());
+    assert_eq!(sampler.sample(c.clone(), &eg, &mut rng), None);
+    assert_eq!(sampler.sample_weighted(c, &eg, |_| 1.0, &mut rng), None);
+}
@@ -22,6 +22,14 @@ fn assert_reaches(start: &str, goal: &str, steps: usize) {
     assert!(false);
 }
 
+// adds x = (add x 0), which gives the e-class of x infinitely many terms.
+pub fn x_plus_zero(eg: &mut EGraph<Arith>) -> AppliedId {
+    let x = eg.add_expr(RecExpr::parse("x").unwrap());
+    let y = eg.add_expr(RecExpr::parse("(add x 0)").unwrap());
+    eg.union(&x, &y);
+    x
+}
+
 
 #[test]
 fn t1() { // x+y = y+x
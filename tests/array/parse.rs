@@ -36,6 +36,8 @@ fn translate(p: Pattern<Sym>) -> Pattern<Array> {
             }
         }
         Pattern::PVar(x) => Pattern::PVar(x),
+        Pattern::PVarExcept(x, slots) => Pattern::PVarExcept(x, slots),
+        Pattern::PVarApplied(x, args) => Pattern::PVarApplied(x, args),
         Pattern::Subst(..) => panic!(),
     }
 }
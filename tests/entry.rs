@@ -43,6 +43,10 @@ pub fn id<L: Language>(s: &str, eg: &mut EGraph<L>) -> AppliedId {
     out
 }
 
+pub fn class_ids<L: Language, N: Analysis<L>>(eg: &EGraph<L, N>) -> Vec<AppliedId> {
+    eg.ids().into_iter().map(|i| AppliedId::new(i, SlotMap::identity(&eg.slots(i)))).collect()
+}
+
 pub fn term<L: Language>(s: &str, eg: &mut EGraph<L>) -> RecExpr<L> {
     let re = RecExpr::parse(s).unwrap();
     re
@@ -54,12 +54,10 @@ fn beta() -> Rewrite<Rise> {
 }
 
 fn eta() -> Rewrite<Rise> {
-    let pat = "(lam $1 (app ?f (var $1)))";
+    let pat = "(lam $1 (app ?f \\ {$1} (var $1)))";
     let outpat = "?f";
 
-    Rewrite::new_if("eta", pat, outpat, |subst| {
-        !subst["f"].slots().contains(&Slot::numeric(1))
-    })
+    Rewrite::new("eta", pat, outpat)
 }
 
 fn eta_expansion() -> Rewrite<Rise> {
@@ -70,11 +68,9 @@ fn eta_expansion() -> Rewrite<Rise> {
 }
 
 fn my_let_unused() -> Rewrite<Rise> {
-    let pat = "(let $1 ?t ?b)";
+    let pat = "(let $1 ?t ?b \\ {$1})";
     let outpat = "?b";
-    Rewrite::new_if("my-let-unused", pat, outpat, |subst| {
-        !subst["b"].slots().contains(&Slot::numeric(1))
-    })
+    Rewrite::new("my-let-unused", pat, outpat)
 }
 
 fn let_var_same() -> Rewrite<Rise> {
@@ -132,7 +128,7 @@ fn let_const() -> Rewrite<Rise> {
             }
         }),
     };
-    rt.into()
+    rt.into().with_name("let-const")
 }
 
 /////////////////////
@@ -149,16 +145,14 @@ fn map_fission() -> Rewrite<Rise> {
     let mfi = 1;
 
     let pat = &format!(
-        "(app map (lam ${x} (app ?f ?gx)))"
+        "(app map (lam ${x} (app ?f \\ {{${x}}} ?gx)))"
     );
 
     let outpat = &format!(
         "(lam ${mfi} (app (app map ?f) (app (app map (lam ${x} ?gx)) (var ${mfi}))))"
     );
 
-    Rewrite::new_if("map-fission", pat, outpat, move |subst| {
-        !subst["f"].slots().contains(&Slot::numeric(x))
-    })
+    Rewrite::new("map-fission", pat, outpat)
 }
 
 fn remove_transpose_pair() -> Rewrite<Rise> {
@@ -247,7 +241,7 @@ fn beta_extr() -> Rewrite<Rise> {
             }
         }),
     };
-    rt.into()
+    rt.into().with_name("beta-extr")
 }
 
 // why is this faster than beta_extr?
@@ -278,7 +272,7 @@ fn beta_extr_direct() -> Rewrite<Rise> {
             }
         }),
     };
-    rt.into()
+    rt.into().with_name("beta-extr-direct")
 }
 
 fn re_subst(s: Slot, b: RecExpr<Rise>, t: &RecExpr<Rise>) -> RecExpr<Rise> {
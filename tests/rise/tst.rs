@@ -1,6 +1,12 @@
 use crate::*;
 
+use std::time::Duration;
+
 fn assert_reaches(start: &str, goal: &str, steps: usize) {
+    assert_reaches_with(start, goal, steps, SimpleScheduler);
+}
+
+fn assert_reaches_with(start: &str, goal: &str, steps: usize, scheduler: impl Scheduler + 'static) {
     let start = RecExpr::parse(start).unwrap();
     let goal = RecExpr::parse(goal).unwrap();
 
@@ -8,19 +14,36 @@ fn assert_reaches(start: &str, goal: &str, steps: usize) {
 
     let mut eg = EGraph::new();
     let i1 = eg.add_expr(start.clone());
-    for _ in 0..steps {
-        apply_rewrites(&mut eg, &rules);
-        dbg!(eg.total_number_of_nodes());
-        if let Some(i2) = lookup_rec_expr(&goal, &eg) {
-            if eg.eq(&i1, &i2) {
-                dbg!(eg.total_number_of_nodes());
-                #[cfg(feature = "explanations")]
-                println!("{}", eg.explain_equivalence(start, goal).to_string(&eg));
-                return;
+
+    let hook_i1 = i1.clone();
+    let hook_goal = goal.clone();
+    let runner = Runner::new(eg)
+        .with_iter_limit(steps)
+        .with_node_limit(usize::MAX)
+        .with_time_limit(Duration::MAX)
+        .with_scheduler(scheduler)
+        .with_hook(move |eg| {
+            dbg!(eg.total_number_of_nodes());
+            if let Some(i2) = lookup_rec_expr(&hook_goal, eg) {
+                if eg.eq(&hook_i1, &i2) {
+                    return Err(String::from("goal reached"));
+                }
             }
+            Ok(())
+        })
+        .run(&rules);
+
+    let eg = runner.egraph;
+    if let Some(StopReason::Other(_)) = runner.stop_reason {
+        #[cfg(feature = "explanations")]
+        {
+            let mut eg = eg;
+            println!("{}", eg.explain_equivalence(start, goal).to_string(&eg));
         }
+        return;
     }
 
+    dbg!(runner.stop_reason);
     dbg!(extract::<_, _, AstSizeNoLet>(i1, &eg));
     dbg!(&goal);
     assert!(false);
@@ -48,6 +71,12 @@ pub fn binomial() {
     assert_reaches(a, b, 40);
 }
 
+#[test]
+fn fission_backoff() {
+    let a = "(app map (lam $42 (app f5 (app f4 (app f3 (app f2 (app f1 (var $42))))))))";
+    let b = "(lam $1 (app (app map (lam $42 (app f5 (app f4 (app f3 (var $42)))))) (app (app map (lam $42 (app f2 (app f1 (var $42))))) (var $1))))";
+    assert_reaches_with(a, b, 40, BackoffScheduler::default().with_initial_match_limit(100));
+}
 
 #[test]
 fn small15() {
@@ -1,13 +1,10 @@
 use crate::*;
 
 pub fn sdql_rules() -> Vec<Rewrite<Sdql>> {
-    let pat = "(sum $x $y ?R (sing ?e1 ?e2))";
+    let pat = "(sum $x $y ?R (sing ?e1 \\ {$x $y} ?e2))";
     let outpat = "(sing ?e1 (sum $x $y ?R ?e2))";
 
-    vec![Rewrite::new_if("rule1", pat, outpat, |subst| {
-        !subst["e1"].slots().contains(&Slot::named("x"))
-        && !subst["e1"].slots().contains(&Slot::named("y"))
-    })]
+    vec![Rewrite::new("rule1", pat, outpat)]
 
     //rw!("sum-fact-3";  "(sum ?R (sing ?e1 ?e2))"        => 
     //        { with_shifted_double_down(var("?e1"), var("?e1d"), 2, "(sing ?e1d (sum ?R ?e2))".parse::<Pattern<SDQL>>().unwrap()) }
@@ -25,7 +22,9 @@ fn t1() {
 
     let id = eg.add_syn_expr(re.clone());
 
-    apply_rewrites(&mut eg, &rewrites);
+    // ?e1 = (var $a) doesn't mention $i or $j, so the rule applies.
+    let changes = apply_rewrites(&mut eg, &rewrites);
+    assert_eq!(changes.unions, 1);
     let term = extract::<_, _, AstSize>(id.clone(), &eg);
     eprintln!("{}", re.to_string());
     eprintln!("{}", term.to_string());
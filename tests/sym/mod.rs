@@ -47,3 +47,19 @@ fn parse_roundtrip() {
     let x: RecExpr<Sym> = RecExpr::parse(s).unwrap();
     assert_eq!(s, &*x.to_string());
 }
+
+#[test]
+fn parse_pattern_roundtrip() {
+    let s = "(foo ?a \\ {$x $y} (bar ?b))";
+
+    let x: Pattern<Sym> = Pattern::parse(s).unwrap();
+    assert_eq!(s, &*x.to_string());
+}
+
+#[test]
+fn parse_higher_order_pattern_roundtrip() {
+    let s = "(foo ?f[$x $y] ?g[] ?b[?x := ?t])";
+
+    let x: Pattern<Sym> = Pattern::parse(s).unwrap();
+    assert_eq!(s, &*x.to_string());
+}